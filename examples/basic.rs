@@ -1,7 +1,7 @@
 extern crate aych_delay;
 
 use aych_delay::{Delay, Settings};
-use rodio::{buffer::SamplesBuffer, Decoder, OutputStream, Sink};
+use rodio::{buffer::SamplesBuffer, Decoder, OutputStream, Sink, Source};
 use std::fs::File;
 use std::path::Path;
 
@@ -22,7 +22,12 @@ fn main() {
     let banner = include_str!("../banner.txt");
     println!("{}", banner);
 
+    let file = File::open(file_name).unwrap();
+    let mut source = Decoder::new_looped(file).unwrap();
+    let sample_rate = source.sample_rate();
+
     let mut delay = Delay::new(Settings {
+        sample_rate: sample_rate as f32,
         delay_time: 166.66,
         feedback: 0.75,
         width: 0.5,
@@ -33,9 +38,6 @@ fn main() {
         ..Settings::default()
     });
 
-    let file = File::open(file_name).unwrap();
-    let mut source = Decoder::new_looped(file).unwrap();
-
     // Get a output stream handle to the default physical sound device
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
 
@@ -53,13 +55,13 @@ fn main() {
         }
 
         // Fill the input buffer with samples from the WAV file
-        for i in 0..input.len() {
-            input[i] = source.next().unwrap_or(0) as f32 / 32768.0;
+        for sample in input.iter_mut() {
+            *sample = source.next().unwrap_or(0) as f32 / 32768.0;
         }
 
-        delay.process(&mut input, &mut output);
+        delay.process(&input, &mut output);
 
         // Play the output buffer
-        sink.append(SamplesBuffer::new(2, 44100, output.clone()));
+        sink.append(SamplesBuffer::new(2, sample_rate, output.clone()));
     }
 }
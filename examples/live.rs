@@ -0,0 +1,108 @@
+extern crate aych_delay;
+
+use aych_delay::{Delay, Settings};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::mpsc;
+
+// The number of stereo frames processed at a time.
+const BLOCK_FRAMES: usize = 256;
+
+fn main() {
+    let host = cpal::default_host();
+    let input_device = host.default_input_device().unwrap_or_else(|| {
+        println!("No input device found");
+        std::process::exit(1);
+    });
+    let output_device = host.default_output_device().unwrap_or_else(|| {
+        println!("No output device found");
+        std::process::exit(1);
+    });
+
+    // Use the input's config for the output too, so both run at the same sample rate.
+    let config = input_device.default_input_config().unwrap_or_else(|err| {
+        eprintln!("Couldn't get the input config: {}", err);
+        std::process::exit(1);
+    });
+    if config.sample_format() != cpal::SampleFormat::F32 {
+        eprintln!(
+            "Unsupported input sample format {:?}, only f32 is supported",
+            config.sample_format()
+        );
+        std::process::exit(1);
+    }
+    let config: cpal::StreamConfig = config.into();
+    let channels = config.channels as usize;
+
+    let banner = include_str!("../banner.txt");
+    println!("{}", banner);
+
+    let mut delay = Delay::new(Settings {
+        sample_rate: config.sample_rate.0 as f32,
+        delay_time: 250.0,
+        feedback: 0.5,
+        width: 0.5,
+        highpass_filter: 300.0,
+        dry_wet_mix: 0.35,
+        ..Settings::default()
+    });
+
+    // The processed frames are handed to the output through a bounded channel,
+    // which is allocated once, up front. Sending whole frames keeps the left and
+    // right channels in step, even when some are dropped.
+    let (sender, receiver) = mpsc::sync_channel::<[f32; 2]>(BLOCK_FRAMES * 8);
+
+    // The blocks are fixed-size arrays, so the callbacks never allocate.
+    let mut input = [0.0; BLOCK_FRAMES * 2];
+    let mut output = [0.0; BLOCK_FRAMES * 2];
+    let mut filled = 0;
+
+    let input_stream = input_device
+        .build_input_stream(
+            &config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                for frame in data.chunks(channels) {
+                    // Mono inputs are copied to both channels.
+                    input[filled] = frame[0];
+                    input[filled + 1] = frame.get(1).copied().unwrap_or(frame[0]);
+                    filled += 2;
+
+                    if filled == input.len() {
+                        delay.process(&input, &mut output);
+                        for frame in output.chunks_exact(2) {
+                            // Drop the frame if the output has fallen behind.
+                            let _ = sender.try_send([frame[0], frame[1]]);
+                        }
+                        filled = 0;
+                    }
+                }
+            },
+            |err| eprintln!("Input stream error: {}", err),
+        )
+        .unwrap();
+
+    let output_stream = output_device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    // Play silence until the input has caught up.
+                    let [left, right] = receiver.try_recv().unwrap_or([0.0; 2]);
+
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        *sample = match channel {
+                            0 => left,
+                            _ => right,
+                        };
+                    }
+                }
+            },
+            |err| eprintln!("Output stream error: {}", err),
+        )
+        .unwrap();
+
+    input_stream.play().unwrap();
+    output_stream.play().unwrap();
+
+    println!("Listening... press Enter to stop.");
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+}
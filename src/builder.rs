@@ -0,0 +1,196 @@
+use crate::{
+    BandParams, ChorusParams, FilterRouting, FilterSlope, FilterType, GranularParams,
+    Interpolation, MixLaw, ModWaveform, PeakParams, PhaseReverse, Saturation, Settings, StereoMode,
+    Tap, TimeChangeMode,
+};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// `SettingsError` describes why a set of [`Settings`] was rejected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingsError {
+    /// The sample rate must be a positive, finite number of Hz.
+    InvalidSampleRate(f32),
+
+    /// Delay times must be positive, finite numbers of milliseconds.
+    InvalidDelayTime(f32),
+
+    /// The resonator's pitch must be a finite number of Hz, of at least 1 Hz.
+    InvalidTune(f32),
+
+    /// Feedback must be at least 0.0 and below 1.0, otherwise the repeats never decay.
+    FeedbackOutOfRange(f32),
+
+    /// The dry/wet mix must be between 0.0 and 1.0.
+    DryWetMixOutOfRange(f32),
+
+    /// The lowpass cutoff must be above the highpass cutoff, otherwise the two
+    /// filters cancel out and the repeats are almost silent.
+    InvertedFilters {
+        /// The lowpass filter's cutoff frequency.
+        lowpass: f64,
+        /// The highpass filter's cutoff frequency.
+        highpass: f64,
+    },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::InvalidSampleRate(sample_rate) => {
+                write!(f, "sample rate must be positive, got {sample_rate} Hz")
+            }
+            SettingsError::InvalidDelayTime(delay_time) => {
+                write!(f, "delay time must be positive, got {delay_time} ms")
+            }
+            SettingsError::InvalidTune(tune_hz) => {
+                write!(f, "resonator pitch must be at least 1 Hz, got {tune_hz} Hz")
+            }
+            SettingsError::FeedbackOutOfRange(feedback) => {
+                write!(
+                    f,
+                    "feedback must be in the range [0.0, 1.0), got {feedback}"
+                )
+            }
+            SettingsError::DryWetMixOutOfRange(dry_wet_mix) => {
+                write!(
+                    f,
+                    "dry/wet mix must be in the range [0.0, 1.0], got {dry_wet_mix}"
+                )
+            }
+            SettingsError::InvertedFilters { lowpass, highpass } => write!(
+                f,
+                "lowpass cutoff ({lowpass} Hz) must be above the highpass cutoff ({highpass} Hz)"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SettingsError {}
+
+/// `SettingsBuilder` builds [`Settings`] with fluent methods, validating them on [`build`].
+///
+/// Any setting that isn't set keeps its default value.
+///
+/// ```rust
+/// use aych_delay::Settings;
+///
+/// let settings = Settings::builder()
+///     .delay_time(166.66)
+///     .feedback(0.75)
+///     .ping_pong(false)
+///     .build()
+///     .unwrap();
+///
+/// assert!(Settings::builder().feedback(1.0).build().is_err());
+/// ```
+///
+/// [`build`]: SettingsBuilder::build
+#[derive(Clone, Debug, Default)]
+pub struct SettingsBuilder {
+    settings: Settings,
+}
+
+macro_rules! setters {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Sets [`Settings::", stringify!($name), "`].")]
+            pub fn $name(mut self, $name: $ty) -> Self {
+                self.settings.$name = $name;
+                self
+            }
+        )*
+    };
+}
+
+impl SettingsBuilder {
+    /// Creates a new `SettingsBuilder`, starting from the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    setters! {
+        sample_rate: f32,
+        delay_time: f32,
+        delay_time_right: Option<f32>,
+        stereo_offset_ms: f32,
+        resonator: bool,
+        tune_hz: f32,
+        time_change_mode: TimeChangeMode,
+        input_gain: f32,
+        output_level: f32,
+        balance: f32,
+        feedback: f32,
+        decay_time: Option<f32>,
+        ping_pong: bool,
+        ping_pong_bounces: Option<u32>,
+        swing: f32,
+        stereo_mode: StereoMode,
+        width: f32,
+        ms_width: f32,
+        cross_feedback: f32,
+        phase_reverse: PhaseReverse,
+        lowpass_filter: f64,
+        lowpass_filter_right: Option<f64>,
+        highpass_filter: f64,
+        highpass_filter_right: Option<f64>,
+        lowpass_enabled: bool,
+        highpass_enabled: bool,
+        filter_slope: FilterSlope,
+        filter_routing: FilterRouting,
+        filter_in_feedback: bool,
+        filter_dry: bool,
+        filter_type: FilterType,
+        resonance: f64,
+        filter_env_amount: f32,
+        filter_env_rate: f32,
+        shelf_gain_db: f32,
+        shelf_freq: f64,
+        repeat_brighten_db: f32,
+        peak_filter: Option<PeakParams>,
+        band_filter: Option<BandParams>,
+        allpass_filter: Option<f64>,
+        diffusion: f32,
+        dry_wet_mix: f32,
+        wet_only: bool,
+        mix_law: MixLaw,
+        chorus: Option<ChorusParams>,
+        dry_haas_ms: f32,
+        interpolation: Interpolation,
+        mod_rate_hz: f32,
+        mod_depth_ms: f32,
+        mod_waveform: ModWaveform,
+        wow_depth_ms: f32,
+        wow_rate_hz: f32,
+        flutter_depth_ms: f32,
+        flutter_rate_hz: f32,
+        reverse: bool,
+        granular: Option<GranularParams>,
+        taps: Vec<Tap>,
+        saturation: Saturation,
+        drive: f32,
+        oversample: u32,
+        bit_depth: Option<u8>,
+        downsample_factor: Option<u32>,
+        auto_gain: bool,
+        dc_block: bool,
+        freeze: bool,
+        duck_amount: f32,
+        duck_attack_ms: f32,
+        duck_release_ms: f32,
+        bypass: bool,
+        smoothing_time_ms: f32,
+        meter_rms_window_ms: f32,
+        meter_release_ms: f32,
+    }
+
+    /// Validates the settings, returning them if they're usable.
+    ///
+    /// See [`Settings::validate`] for the checks that are made.
+    pub fn build(self) -> Result<Settings, SettingsError> {
+        self.settings.validate()?;
+
+        Ok(self.settings)
+    }
+}
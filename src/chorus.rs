@@ -0,0 +1,139 @@
+use crate::math::{floorf, sinf};
+use crate::{Frame, Sample};
+use alloc::{vec, vec::Vec};
+use core::f32::consts::TAU;
+
+// The delay each voice sweeps around, in milliseconds.
+const BASE_DELAY_MS: f32 = 15.0;
+
+// The deepest sweep a voice can make either side of the base delay, in milliseconds.
+const MAX_DEPTH_MS: f32 = 10.0;
+
+// The number of voices per channel, spread evenly around the LFO cycle.
+const VOICES: usize = 3;
+
+// The depth, rate and mix used in place of a NaN or infinite parameter, which
+// would otherwise stick in the LFO phase and silence the dry signal for good.
+const DEFAULT_DEPTH_MS: f32 = 3.0;
+const DEFAULT_RATE_HZ: f32 = 0.5;
+const DEFAULT_MIX: f32 = 0.5;
+
+// Returns `value`, or `default` if it isn't finite.
+fn finite_or(value: f32, default: f32) -> f32 {
+    match value.is_finite() {
+        true => value,
+        false => default,
+    }
+}
+
+/// `ChorusParams` are the parameters of the chorus on the dry signal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChorusParams {
+    /// How far each voice sweeps either side of its delay, in milliseconds, up to 10 ms.
+    pub depth_ms: f32,
+
+    /// How quickly the voices sweep, in Hz.
+    pub rate_hz: f32,
+
+    /// The level of the voices against the dry signal, between 0.0 and 1.0.
+    pub mix: f32,
+}
+
+impl ChorusParams {
+    /// Creates new `ChorusParams` from a depth and rate, with an even mix.
+    pub fn new(depth_ms: f32, rate_hz: f32) -> Self {
+        Self {
+            depth_ms,
+            rate_hz,
+            mix: DEFAULT_MIX,
+        }
+    }
+}
+
+// A multi-voice chorus, made of short delays swept by LFOs that are out of
+// phase with each other, so the voices never line up.
+#[derive(Clone)]
+pub(crate) struct Chorus<T> {
+    buffer: Vec<Frame<T>>,
+    index: usize,
+    phase: f32,
+}
+
+impl<T: Sample> Chorus<T> {
+    pub fn new(sample_rate: f32) -> Self {
+        // Two extra samples for the interpolated read of the longest delay.
+        let len = ((BASE_DELAY_MS + MAX_DEPTH_MS) / 1000.0 * sample_rate) as usize + 2;
+
+        Self {
+            buffer: vec![Frame::default(); len],
+            index: 0,
+            phase: 0.0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.fill(Frame::default());
+        self.index = 0;
+        self.phase = 0.0;
+    }
+
+    // Reads the buffer `delay` samples back, interpolating linearly.
+    fn read(&self, delay: f32) -> Frame<T> {
+        let len = self.buffer.len();
+        let whole = delay as usize;
+        let fraction = T::from_f32(delay - whole as f32);
+        let tap = |delay: usize| self.buffer[(self.index + len - delay.min(len - 1)) % len];
+
+        tap(whole) * (T::ONE - fraction) + tap(whole + 1) * fraction
+    }
+
+    pub fn process(&mut self, input: Frame<T>, params: ChorusParams, sample_rate: f32) -> Frame<T> {
+        self.buffer[self.index] = input;
+
+        let depth = finite_or(params.depth_ms, DEFAULT_DEPTH_MS).clamp(0.0, MAX_DEPTH_MS);
+        let voice_delay = |phase: f32| {
+            let delay_ms = BASE_DELAY_MS + depth * sinf(phase * TAU);
+
+            delay_ms / 1000.0 * sample_rate
+        };
+
+        // The right channel's voices are a quarter of a cycle behind the left's,
+        // which spreads the chorus across the stereo image.
+        let voices = (0..VOICES).fold(Frame::default(), |sum, voice| {
+            let phase = self.phase + voice as f32 / VOICES as f32;
+
+            sum + Frame::new(
+                self.read(voice_delay(phase)).l,
+                self.read(voice_delay(phase + 0.25)).r,
+            )
+        });
+        let voices = voices * T::from_f32(1.0 / VOICES as f32);
+
+        self.index = (self.index + 1) % self.buffer.len();
+        let phase = self.phase + finite_or(params.rate_hz, DEFAULT_RATE_HZ) / sample_rate;
+        self.phase = phase - floorf(phase);
+
+        let mix = T::from_f32(finite_or(params.mix, DEFAULT_MIX).clamp(0.0, 1.0));
+        input * (T::ONE - mix) + voices * mix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_params_fall_back_to_defaults() {
+        let mut chorus = Chorus::<f32>::new(48000.0);
+        let params = ChorusParams {
+            depth_ms: f32::NAN,
+            rate_hz: f32::INFINITY,
+            mix: f32::NAN,
+        };
+        for _ in 0..4800 {
+            let output = chorus.process(Frame::new(0.5, -0.5), params, 48000.0);
+            assert!(output.l.is_finite() && output.r.is_finite());
+        }
+    }
+}
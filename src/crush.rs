@@ -0,0 +1,39 @@
+use crate::math::floor;
+use crate::{Frame, Sample};
+
+// Rounds a sample to the nearest step of a signed `bits`-bit quantizer.
+pub(crate) fn quantize<T: Sample>(input: T, bits: u8) -> T {
+    let steps = (1u32 << (bits.clamp(1, 24) - 1)) as f64;
+
+    T::from_f64(floor(input.to_f64() * steps + 0.5) / steps)
+}
+
+// Reduces the sample rate by holding each sample for a number of samples,
+// without any anti-aliasing, for a gritty lo-fi sound.
+#[derive(Clone)]
+pub(crate) struct Decimator<T> {
+    held: Frame<T>,
+    counter: u32,
+}
+
+impl<T: Sample> Decimator<T> {
+    pub fn new() -> Self {
+        Self {
+            held: Frame::default(),
+            counter: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn process(&mut self, input: Frame<T>, factor: u32) -> Frame<T> {
+        if self.counter == 0 {
+            self.held = input;
+        }
+        self.counter = (self.counter + 1) % factor.max(1);
+
+        self.held
+    }
+}
@@ -0,0 +1,70 @@
+use crate::smoother::get_coefficient;
+
+/// `EnvelopeFollower` tracks the level of a signal, rising over the attack time
+/// and falling over the release time.
+///
+/// It's what the delay uses to duck the repeats and to open the filter envelope,
+/// and it can be used on its own to follow the level of any signal.
+///
+/// ```rust
+/// use aych_delay::EnvelopeFollower;
+///
+/// let mut follower = EnvelopeFollower::new(5.0, 100.0, 48000.0);
+///
+/// let level = (0..4800).fold(0.0, |_, _| follower.process(0.5));
+/// assert!((level - 0.5).abs() < 0.01);
+/// ```
+#[derive(Clone, Debug)]
+pub struct EnvelopeFollower {
+    envelope: f32,
+    attack: f32,
+    release: f32,
+    times: (f32, f32, f32),
+}
+
+impl EnvelopeFollower {
+    /// Creates a new `EnvelopeFollower` with the attack and release times in
+    /// milliseconds, starting from silence.
+    pub fn new(attack_ms: f32, release_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            envelope: 0.0,
+            attack: get_coefficient(attack_ms, sample_rate),
+            release: get_coefficient(release_ms, sample_rate),
+            times: (attack_ms, release_ms, sample_rate),
+        }
+    }
+
+    /// Changes the attack and release times, without resetting the envelope.
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32, sample_rate: f32) {
+        // Only recompute the coefficients when the times change.
+        if self.times != (attack_ms, release_ms, sample_rate) {
+            *self = Self {
+                envelope: self.envelope,
+                ..Self::new(attack_ms, release_ms, sample_rate)
+            };
+        }
+    }
+
+    /// Returns the current level of the envelope, without advancing it.
+    pub fn envelope(&self) -> f32 {
+        self.envelope
+    }
+
+    /// Resets the envelope to silence.
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+
+    /// Follows the level of one sample, returning the updated envelope.
+    pub fn process(&mut self, input: f32) -> f32 {
+        let level = input.abs();
+        let coefficient = match level > self.envelope {
+            true => self.attack,
+            false => self.release,
+        };
+
+        self.envelope += (level - self.envelope) * coefficient;
+
+        self.envelope
+    }
+}
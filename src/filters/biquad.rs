@@ -0,0 +1,163 @@
+// Biquad filter, in transposed direct form II.
+//
+// The coefficients are from Robert Bristow-Johnson's "Audio EQ Cookbook".
+// https://www.w3.org/TR/audio-eq-cookbook/
+
+use crate::filters::{Filter, Mode};
+use crate::math::{cos, pow, sin, sqrt};
+use crate::util::flush_denormal;
+use crate::{Frame, Sample};
+use core::f64::consts::PI;
+
+const MIN_FREQ: f64 = 5.0;
+const MAX_FREQ: f64 = 22000.0;
+const NORMALIZED_FREQ_LIMIT: f64 = 0.49;
+const MIN_Q: f64 = 0.1;
+
+#[derive(Clone)]
+pub struct Biquad<T> {
+    // Normalized by a0.
+    b: [f64; 3],
+    a: [f64; 2],
+    z1: T,
+    z2: T,
+}
+
+impl<T: Sample> Biquad<T> {
+    // `gain_db` is only used by the shelving and peaking modes.
+    pub fn new(mode: Mode, sample_rate: f64, freq_hz: f64, q: f64, gain_db: f64) -> Self {
+        // Clamp the frequency to the Nyquist frequency
+        let freq_hz = freq_hz
+            .max(MIN_FREQ)
+            .min(MAX_FREQ.min(sample_rate * NORMALIZED_FREQ_LIMIT));
+
+        let a = pow(10.0, gain_db / 40.0);
+        let w0 = 2.0 * PI * freq_hz / sample_rate;
+        let (sin_w0, cos_w0) = (sin(w0), cos(w0));
+        let alpha = sin_w0 / (2.0 * q.max(MIN_Q));
+        let shelf = 2.0 * sqrt(a) * alpha;
+
+        let (b, a) = match mode {
+            Mode::LOWPASS => (
+                [(1.0 - cos_w0) / 2.0, 1.0 - cos_w0, (1.0 - cos_w0) / 2.0],
+                [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+            ),
+            Mode::HIGHPASS => (
+                [(1.0 + cos_w0) / 2.0, -(1.0 + cos_w0), (1.0 + cos_w0) / 2.0],
+                [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+            ),
+            Mode::ALLPASS => (
+                [1.0 - alpha, -2.0 * cos_w0, 1.0 + alpha],
+                [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+            ),
+            Mode::LOWSHELF => (
+                [
+                    a * ((a + 1.0) - (a - 1.0) * cos_w0 + shelf),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+                    a * ((a + 1.0) - (a - 1.0) * cos_w0 - shelf),
+                ],
+                [
+                    (a + 1.0) + (a - 1.0) * cos_w0 + shelf,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+                    (a + 1.0) + (a - 1.0) * cos_w0 - shelf,
+                ],
+            ),
+            Mode::HIGHSHELF => (
+                [
+                    a * ((a + 1.0) + (a - 1.0) * cos_w0 + shelf),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+                    a * ((a + 1.0) + (a - 1.0) * cos_w0 - shelf),
+                ],
+                [
+                    (a + 1.0) - (a - 1.0) * cos_w0 + shelf,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+                    (a + 1.0) - (a - 1.0) * cos_w0 - shelf,
+                ],
+            ),
+            Mode::PEAK => (
+                [1.0 + alpha * a, -2.0 * cos_w0, 1.0 - alpha * a],
+                [1.0 + alpha / a, -2.0 * cos_w0, 1.0 - alpha / a],
+            ),
+            // A constant 0 dB peak gain bandpass.
+            Mode::BANDPASS => (
+                [alpha, 0.0, -alpha],
+                [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+            ),
+            Mode::NOTCH => (
+                [1.0, -2.0 * cos_w0, 1.0],
+                [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+            ),
+        };
+
+        Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            z1: T::ZERO,
+            z2: T::ZERO,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.z1 = T::ZERO;
+        self.z2 = T::ZERO;
+    }
+
+    // Returns the linear gain of the filter at `freq_hz`, from its coefficients.
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        let w = 2.0 * PI * freq_hz.clamp(0.0, sample_rate / 2.0) / sample_rate;
+        let (cos_w, sin_w) = (cos(w), sin(w));
+        let (cos_2w, sin_2w) = (cos(2.0 * w), sin(2.0 * w));
+
+        // Evaluate the numerator and denominator at z = e^(jw).
+        let [b0, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let numerator = (b0 + b1 * cos_w + b2 * cos_2w, b1 * sin_w + b2 * sin_2w);
+        let denominator = (1.0 + a1 * cos_w + a2 * cos_2w, a1 * sin_w + a2 * sin_2w);
+
+        sqrt(numerator.0 * numerator.0 + numerator.1 * numerator.1)
+            / sqrt(denominator.0 * denominator.0 + denominator.1 * denominator.1)
+    }
+}
+
+impl<T: Sample> Filter<T> for Biquad<T> {
+    fn process(&mut self, input: T) -> T {
+        let [b0, b1, b2] = self.b.map(T::from_f64);
+        let [a1, a2] = self.a.map(T::from_f64);
+
+        let output = b0 * input + self.z1;
+        self.z1 = flush_denormal(b1 * input - a1 * output + self.z2);
+        self.z2 = flush_denormal(b2 * input - a2 * output);
+
+        output
+    }
+}
+
+#[derive(Clone)]
+pub struct BiquadStereo<T> {
+    left: Biquad<T>,
+    right: Biquad<T>,
+}
+
+impl<T: Sample> BiquadStereo<T> {
+    pub fn new(mode: Mode, sample_rate: f64, freq_hz: f64, q: f64, gain_db: f64) -> Self {
+        let filter = Biquad::new(mode, sample_rate, freq_hz, q, gain_db);
+
+        Self {
+            left: filter.clone(),
+            right: filter,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        self.left.magnitude_response(freq_hz, sample_rate)
+    }
+
+    pub fn process(&mut self, input: Frame<T>) -> Frame<T> {
+        Frame::new(self.left.process(input.l), self.right.process(input.r))
+    }
+}
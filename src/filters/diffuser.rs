@@ -0,0 +1,99 @@
+// A chain of Schroeder allpass filters, which smear each repeat in time
+// without colouring its frequency response.
+
+use crate::util::flush_denormal;
+use crate::{Frame, Sample};
+use alloc::{vec, vec::Vec};
+
+// The delay of each allpass in the chain, in milliseconds. They're mutually
+// prime-ish so the echoes don't pile up, and the right channel's are slightly
+// longer so the two channels decorrelate.
+const STAGE_MS: [f32; 4] = [1.47, 2.23, 3.61, 4.91];
+const RIGHT_SPREAD: f32 = 1.07;
+
+// The allpass coefficient at full diffusion. Higher values ring metallically.
+const MAX_COEFFICIENT: f32 = 0.7;
+
+// Returns how many samples long the allpass of a stage is.
+fn stage_len(delay_ms: f32, sample_rate: f32) -> usize {
+    ((delay_ms / 1000.0 * sample_rate) as usize).max(1)
+}
+
+// Returns how many samples the left and right chains delay the signal by. At
+// a coefficient of 0.0 they're just that delay.
+pub fn latency(sample_rate: f32) -> (f32, f32) {
+    let chain_len = |spread: f32| {
+        STAGE_MS
+            .iter()
+            .map(|delay_ms| stage_len(delay_ms * spread, sample_rate))
+            .sum::<usize>() as f32
+    };
+
+    (chain_len(1.0), chain_len(RIGHT_SPREAD))
+}
+
+#[derive(Clone)]
+struct Allpass<T> {
+    buffer: Vec<T>,
+    index: usize,
+}
+
+impl<T: Sample> Allpass<T> {
+    fn new(delay_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            buffer: vec![T::ZERO; stage_len(delay_ms, sample_rate)],
+            index: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(T::ZERO);
+        self.index = 0;
+    }
+
+    fn process(&mut self, input: T, coefficient: T) -> T {
+        let delayed = self.buffer[self.index];
+        let w = input + delayed * coefficient;
+        self.buffer[self.index] = flush_denormal(w);
+        self.index = (self.index + 1) % self.buffer.len();
+
+        delayed - w * coefficient
+    }
+}
+
+#[derive(Clone)]
+pub struct Diffuser<T> {
+    left: [Allpass<T>; 4],
+    right: [Allpass<T>; 4],
+}
+
+impl<T: Sample> Diffuser<T> {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            left: STAGE_MS.map(|delay_ms| Allpass::new(delay_ms, sample_rate)),
+            right: STAGE_MS.map(|delay_ms| Allpass::new(delay_ms * RIGHT_SPREAD, sample_rate)),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.left.iter_mut().chain(self.right.iter_mut()) {
+            stage.reset();
+        }
+    }
+
+    // `diffusion` from 0.0 to 1.0 sets how much each stage smears the signal.
+    pub fn process(&mut self, input: Frame<T>, diffusion: f32) -> Frame<T> {
+        let coefficient = T::from_f32(diffusion.clamp(0.0, 1.0) * MAX_COEFFICIENT);
+
+        let l = self
+            .left
+            .iter_mut()
+            .fold(input.l, |sample, stage| stage.process(sample, coefficient));
+        let r = self
+            .right
+            .iter_mut()
+            .fold(input.r, |sample, stage| stage.process(sample, coefficient));
+
+        Frame::new(l, r)
+    }
+}
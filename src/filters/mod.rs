@@ -1,16 +1,225 @@
+mod biquad;
+pub(crate) mod diffuser;
+mod statevariable;
 mod tptonepole;
 
-pub use tptonepole::{TPTOnePole, TPTOnePoleStereo};
+pub use biquad::BiquadStereo;
+pub use diffuser::Diffuser;
+pub use statevariable::StateVariableStereo;
+pub use tptonepole::TPTOnePoleStereo;
 
-pub trait Filter {
-    fn process(&mut self, input: f32) -> f32;
+use crate::{Frame, Sample};
+
+/// `FilterType` is the filter topology used for the lowpass and highpass filters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterType {
+    /// A gentle one-pole filter, with no resonance.
+    OnePole,
+
+    /// A two-pole state-variable filter, with a resonant peak at the cutoff.
+    /// It always rolls off at 12 dB/oct, regardless of the filter slope.
+    Svf,
 }
 
-#[derive(Clone)]
+/// `FilterRouting` is how the lowpass and highpass filters are chained in the feedback path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterRouting {
+    /// The lowpass filter, followed by the highpass filter.
+    LpThenHp,
+
+    /// The highpass filter, followed by the lowpass filter.
+    HpThenLp,
+
+    /// Both filters side by side, each filtering the repeats, averaged together.
+    Parallel,
+}
+
+/// `PeakParams` are the parameters of a peaking (bell) EQ filter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeakParams {
+    /// The centre frequency of the bell, in Hz.
+    pub freq_hz: f64,
+
+    /// The width of the bell. Higher values make a narrower bell.
+    pub q: f64,
+
+    /// How much to boost, or cut with negative values, at the centre frequency, in decibels.
+    pub gain_db: f64,
+}
+
+impl PeakParams {
+    /// Creates new `PeakParams` from a centre frequency, Q and gain.
+    pub fn new(freq_hz: f64, q: f64, gain_db: f64) -> Self {
+        Self {
+            freq_hz,
+            q,
+            gain_db,
+        }
+    }
+}
+
+/// `BandParams` are the parameters of a bandpass or notch filter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BandParams {
+    /// The centre frequency of the band, in Hz.
+    pub freq_hz: f64,
+
+    /// The width of the band. Higher values make a narrower band.
+    pub q: f64,
+
+    /// Whether to cut the band out, as a notch, rather than keep only the band.
+    pub notch: bool,
+}
+
+impl BandParams {
+    /// Creates new `BandParams` for a bandpass filter, which keeps only the band
+    /// around `freq_hz`.
+    pub fn bandpass(freq_hz: f64, q: f64) -> Self {
+        Self {
+            freq_hz,
+            q,
+            notch: false,
+        }
+    }
+
+    /// Creates new `BandParams` for a notch filter, which cuts out the band around
+    /// `freq_hz`.
+    pub fn notch(freq_hz: f64, q: f64) -> Self {
+        Self {
+            freq_hz,
+            q,
+            notch: true,
+        }
+    }
+
+    // Returns the filter mode for the band.
+    pub(crate) fn mode(&self) -> Mode {
+        match self.notch {
+            true => Mode::NOTCH,
+            false => Mode::BANDPASS,
+        }
+    }
+}
+
+/// `FilterSlope` is how steeply the lowpass and highpass filters roll off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterSlope {
+    /// 6 dB per octave, from a single one-pole stage.
+    Slope6,
+
+    /// 12 dB per octave, from two cascaded one-pole stages.
+    Slope12,
+
+    /// 24 dB per octave, from four cascaded one-pole stages.
+    Slope24,
+}
+
+impl FilterSlope {
+    // Returns the number of one-pole stages needed for the slope.
+    pub(crate) fn stages(self) -> usize {
+        match self {
+            FilterSlope::Slope6 => 1,
+            FilterSlope::Slope12 => 2,
+            FilterSlope::Slope24 => 4,
+        }
+    }
+}
+
+pub trait Filter<T: Sample> {
+    fn process(&mut self, input: T) -> T;
+
+    // Processes a whole buffer in place, one sample at a time by default.
+    fn process_block(&mut self, buf: &mut [T]) {
+        for sample in buf.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Mode {
     LOWPASS,
     HIGHPASS,
-
-    #[allow(dead_code)]
     ALLPASS,
+    LOWSHELF,
+    HIGHSHELF,
+    PEAK,
+    BANDPASS,
+    NOTCH,
+}
+
+// A stereo filter of either topology, chosen at runtime.
+#[derive(Clone)]
+pub enum StereoFilter<T> {
+    OnePole(TPTOnePoleStereo<T>),
+    Svf(StateVariableStereo<T>),
+}
+
+impl<T: Sample> StereoFilter<T> {
+    pub fn new(
+        filter_type: FilterType,
+        mode: Mode,
+        sample_rate: f64,
+        freq_hz: f64,
+        slope: FilterSlope,
+        resonance: f64,
+    ) -> Self {
+        match filter_type {
+            FilterType::OnePole => StereoFilter::OnePole(TPTOnePoleStereo::with_stages(
+                mode,
+                sample_rate,
+                freq_hz,
+                slope.stages(),
+            )),
+            FilterType::Svf => StereoFilter::Svf(StateVariableStereo::new(
+                mode,
+                sample_rate,
+                freq_hz,
+                resonance,
+            )),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            StereoFilter::OnePole(filter) => filter.reset(),
+            StereoFilter::Svf(filter) => filter.reset(),
+        }
+    }
+
+    // Moves the left and right cutoffs without clearing the filter state.
+    pub fn set_cutoffs(&mut self, sample_rate: f64, (left_hz, right_hz): (f64, f64)) {
+        match self {
+            StereoFilter::OnePole(filter) => filter.set_cutoffs(sample_rate, left_hz, right_hz),
+            StereoFilter::Svf(filter) => filter.set_cutoffs(sample_rate, left_hz, right_hz),
+        }
+    }
+
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        match self {
+            StereoFilter::OnePole(filter) => filter.magnitude_response(freq_hz, sample_rate),
+            StereoFilter::Svf(filter) => filter.magnitude_response(freq_hz, sample_rate),
+        }
+    }
+
+    pub fn process(&mut self, input: Frame<T>) -> Frame<T> {
+        match self {
+            StereoFilter::OnePole(filter) => filter.process(input),
+            StereoFilter::Svf(filter) => filter.process(input),
+        }
+    }
+
+    // Filters separate left and right buffers in place.
+    pub fn process_block(&mut self, l: &mut [T], r: &mut [T]) {
+        match self {
+            StereoFilter::OnePole(filter) => filter.process_block(l, r),
+            StereoFilter::Svf(filter) => filter.process_block(l, r),
+        }
+    }
 }
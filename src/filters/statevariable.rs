@@ -0,0 +1,155 @@
+// "Topology preserving transform" state-variable filter.
+//
+// Derived from Andrew Simper's (Cytomic) linear trapezoidal SVF,
+// which is built on the same TPT approach described by Zavalishin.
+// https://cytomic.com/files/dsp/SvfLinearTrapOptimised2.pdf
+
+use crate::filters::{Filter, Mode};
+use crate::math::{sqrt, tan};
+use crate::util::flush_denormal;
+use crate::{Frame, Sample};
+use core::f64::consts::PI;
+
+const MIN_FREQ: f64 = 5.0;
+const MAX_FREQ: f64 = 22000.0;
+const NORMALIZED_FREQ_LIMIT: f64 = 0.49;
+const MIN_RESONANCE: f64 = 0.1;
+
+#[derive(Clone)]
+pub struct StateVariable<T> {
+    mode: Mode,
+    g: f64,
+    k: T,
+    a1: T,
+    a2: T,
+    a3: T,
+    ic1eq: T,
+    ic2eq: T,
+}
+
+impl<T: Sample> StateVariable<T> {
+    pub fn new(mode: Mode, sample_rate: f64, freq_hz: f64, resonance: f64) -> Self {
+        let mut filter = Self {
+            mode,
+            g: 0.0,
+            k: T::from_f64(1.0 / resonance.max(MIN_RESONANCE)),
+            a1: T::ZERO,
+            a2: T::ZERO,
+            a3: T::ZERO,
+            ic1eq: T::ZERO,
+            ic2eq: T::ZERO,
+        };
+        filter.set_cutoff(sample_rate, freq_hz);
+
+        filter
+    }
+
+    // Moves the cutoff without clearing the filter state, so it can be swept while running.
+    pub fn set_cutoff(&mut self, sample_rate: f64, freq_hz: f64) {
+        // Clamp the frequency to the Nyquist frequency
+        let freq_hz = freq_hz
+            .max(MIN_FREQ)
+            .min(MAX_FREQ.min(sample_rate * NORMALIZED_FREQ_LIMIT));
+
+        let g = tan(PI * freq_hz / sample_rate);
+        let k = self.k.to_f64();
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        self.g = g;
+        self.a1 = T::from_f64(a1);
+        self.a2 = T::from_f64(a2);
+        self.a3 = T::from_f64(a3);
+    }
+
+    pub fn reset(&mut self) {
+        self.ic1eq = T::ZERO;
+        self.ic2eq = T::ZERO;
+    }
+
+    // Returns the linear gain of the filter at `freq_hz`, from its coefficients.
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        // The filter is the bilinear transform of an analog two-pole, so its
+        // response is the analog one's at the prewarped frequency.
+        let w = tan(PI * freq_hz.clamp(0.0, sample_rate / 2.0) / sample_rate) / self.g;
+        let k = self.k.to_f64();
+        let denominator = sqrt((1.0 - w * w) * (1.0 - w * w) + k * k * w * w);
+
+        match self.mode {
+            Mode::LOWPASS => 1.0 / denominator,
+            Mode::HIGHPASS => w * w / denominator,
+            Mode::BANDPASS => k * w / denominator,
+            Mode::NOTCH => (1.0 - w * w).abs() / denominator,
+            Mode::ALLPASS | Mode::LOWSHELF | Mode::HIGHSHELF | Mode::PEAK => 1.0,
+        }
+    }
+
+    // Runs the filter, returning the (lowpass, bandpass, highpass) outputs.
+    fn tick(&mut self, input: T) -> (T, T, T) {
+        let v3 = input - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+        self.ic1eq = flush_denormal(v1 + v1 - self.ic1eq);
+        self.ic2eq = flush_denormal(v2 + v2 - self.ic2eq);
+
+        (v2, v1, input - self.k * v1 - v2)
+    }
+}
+
+impl<T: Sample> Filter<T> for StateVariable<T> {
+    fn process(&mut self, input: T) -> T {
+        let (lpf, bpf, hpf) = self.tick(input);
+
+        match self.mode {
+            Mode::LOWPASS => lpf,
+            Mode::HIGHPASS => hpf,
+            // Normalized to unity gain at the centre, whatever the resonance.
+            Mode::BANDPASS => self.k * bpf,
+            Mode::NOTCH => lpf + hpf,
+            Mode::ALLPASS => lpf - self.k * bpf + hpf,
+            // Shelving and peaking are offered by the other filters.
+            Mode::LOWSHELF | Mode::HIGHSHELF | Mode::PEAK => input,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StateVariableStereo<T> {
+    left: StateVariable<T>,
+    right: StateVariable<T>,
+}
+
+impl<T: Sample> StateVariableStereo<T> {
+    pub fn new(mode: Mode, sample_rate: f64, freq_hz: f64, resonance: f64) -> Self {
+        let filter = StateVariable::new(mode, sample_rate, freq_hz, resonance);
+
+        Self {
+            left: filter.clone(),
+            right: filter,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+
+    pub fn set_cutoffs(&mut self, sample_rate: f64, left_hz: f64, right_hz: f64) {
+        self.left.set_cutoff(sample_rate, left_hz);
+        self.right.set_cutoff(sample_rate, right_hz);
+    }
+
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        self.left.magnitude_response(freq_hz, sample_rate)
+    }
+
+    pub fn process(&mut self, input: Frame<T>) -> Frame<T> {
+        Frame::new(self.left.process(input.l), self.right.process(input.r))
+    }
+
+    pub fn process_block(&mut self, l: &mut [T], r: &mut [T]) {
+        self.left.process_block(l);
+        self.right.process_block(r);
+    }
+}
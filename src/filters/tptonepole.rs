@@ -5,83 +5,248 @@
 // https://github.com/soul-lang/SOUL
 
 use crate::filters::{Filter, Mode};
-use std::f64::consts::PI;
+use crate::math::{pow, sqrt, tan};
+use crate::util::flush_denormal;
+use crate::{Frame, Sample};
+use core::f64::consts::PI;
 
 const MIN_FREQ: f64 = 5.0;
 const MAX_FREQ: f64 = 22000.0;
 const NORMALIZED_FREQ_LIMIT: f64 = 0.49;
 
+// The most one-pole stages a stereo filter can cascade.
+const MAX_STAGES: usize = 4;
+
 fn get_coefficient(sample_rate: f64, freq_hz: f64) -> f64 {
+    // Clamp the frequency to the Nyquist frequency
+    let freq_hz = freq_hz
+        .max(MIN_FREQ)
+        .min(MAX_FREQ.min(sample_rate * NORMALIZED_FREQ_LIMIT));
+
     let wd = 2.0 * PI * freq_hz;
     let t = 1.0 / sample_rate;
-    let wa = (2.0 / t) * (wd * t / 2.0).tan();
+    let wa = (2.0 / t) * tan(wd * t / 2.0);
     let g = wa * t / 2.0;
 
     g / (1.0 + g)
 }
 
-pub struct TPTOnePole {
+#[derive(Clone)]
+pub struct TPTOnePole<T> {
     mode: Mode,
     b: f64,
-    z1: f32,
+    // The linear gain of the shelving modes.
+    shelf_gain: f64,
+    z1: T,
 }
 
-impl TPTOnePole {
+impl<T: Sample> TPTOnePole<T> {
     pub fn new(mode: Mode, sample_rate: f64, freq_hz: f64) -> Self {
-        // Clamp the frequency to the Nyquist frequency
-        let freq_hz = freq_hz.max(MIN_FREQ).min(MAX_FREQ * NORMALIZED_FREQ_LIMIT);
+        Self::with_gain(mode, sample_rate, freq_hz, 0.0)
+    }
 
+    // `gain_db` is the boost or cut of the shelving modes, and is ignored by the others.
+    pub fn with_gain(mode: Mode, sample_rate: f64, freq_hz: f64, gain_db: f64) -> Self {
         Self {
             mode,
             b: get_coefficient(sample_rate, freq_hz),
-            z1: 0.0,
+            shelf_gain: pow(10.0, gain_db / 20.0),
+            z1: T::ZERO,
         }
     }
 
-    fn process_lpf(&mut self, input: f32) -> f32 {
-        let vn = (input - self.z1) * self.b as f32;
+    pub fn reset(&mut self) {
+        self.z1 = T::ZERO;
+    }
+
+    // Moves the cutoff without clearing the filter state, so it can be swept while running.
+    pub fn set_cutoff(&mut self, sample_rate: f64, freq_hz: f64) {
+        self.b = get_coefficient(sample_rate, freq_hz);
+    }
+
+    // Returns the linear gain of the filter at `freq_hz`, from its coefficient.
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        // The filter is the bilinear transform of an analog one-pole, so its
+        // response is the analog one's at the prewarped frequency.
+        let g = self.b / (1.0 - self.b);
+        let w = tan(PI * freq_hz.clamp(0.0, sample_rate / 2.0) / sample_rate) / g;
+        let a = self.shelf_gain;
+        let denominator = sqrt(1.0 + w * w);
+
+        match self.mode {
+            Mode::LOWPASS => 1.0 / denominator,
+            Mode::HIGHPASS => w / denominator,
+            Mode::ALLPASS => 1.0,
+            Mode::LOWSHELF => sqrt(a * a + w * w) / denominator,
+            Mode::HIGHSHELF => sqrt(1.0 + a * a * w * w) / denominator,
+            Mode::PEAK | Mode::BANDPASS | Mode::NOTCH => 1.0,
+        }
+    }
+
+    fn process_lpf(&mut self, input: T) -> T {
+        let vn = (input - self.z1) * T::from_f64(self.b);
         let lpf = vn + self.z1;
-        self.z1 = vn + lpf;
+        self.z1 = flush_denormal(vn + lpf);
 
         lpf
     }
 
-    fn process_hpf(&mut self, input: f32) -> f32 {
+    fn process_hpf(&mut self, input: T) -> T {
         input - self.process_lpf(input)
     }
 
-    fn process_apf(&mut self, input: f32) -> f32 {
+    fn process_apf(&mut self, input: T) -> T {
         let lpf = self.process_lpf(input);
         let hpf = input - lpf;
 
         lpf - hpf
     }
+
+    // Boosts or cuts everything below the cutoff, by mixing in the lowpassed signal.
+    fn process_lsf(&mut self, input: T) -> T {
+        let lpf = self.process_lpf(input);
+
+        input + lpf * T::from_f64(self.shelf_gain - 1.0)
+    }
+
+    // Boosts or cuts everything above the cutoff, by mixing in the highpassed signal.
+    fn process_hsf(&mut self, input: T) -> T {
+        let hpf = self.process_hpf(input);
+
+        input + hpf * T::from_f64(self.shelf_gain - 1.0)
+    }
 }
 
-impl Filter for TPTOnePole {
-    fn process(&mut self, input: f32) -> f32 {
+impl<T: Sample> Filter<T> for TPTOnePole<T> {
+    fn process(&mut self, input: T) -> T {
         match self.mode {
             Mode::LOWPASS => self.process_lpf(input),
             Mode::HIGHPASS => self.process_hpf(input),
             Mode::ALLPASS => self.process_apf(input),
+            Mode::LOWSHELF => self.process_lsf(input),
+            Mode::HIGHSHELF => self.process_hsf(input),
+            // Peaking, bandpass and notch are offered by the other filters.
+            Mode::PEAK | Mode::BANDPASS | Mode::NOTCH => input,
         }
     }
+
+    fn process_block(&mut self, buf: &mut [T]) {
+        // Hoist the coefficient and state out of the loop, and only check the mode once.
+        let b = T::from_f64(self.b);
+        let shelf_gain = T::from_f64(self.shelf_gain - 1.0);
+        let mut z1 = self.z1;
+
+        let mut lpf = |input: T| {
+            let vn = (input - z1) * b;
+            let lpf = vn + z1;
+            z1 = flush_denormal(vn + lpf);
+
+            lpf
+        };
+
+        match self.mode {
+            Mode::LOWPASS => buf.iter_mut().for_each(|sample| *sample = lpf(*sample)),
+            Mode::HIGHPASS => buf
+                .iter_mut()
+                .for_each(|sample| *sample = *sample - lpf(*sample)),
+            Mode::ALLPASS => buf.iter_mut().for_each(|sample| {
+                let lpf = lpf(*sample);
+                *sample = lpf - (*sample - lpf);
+            }),
+            Mode::LOWSHELF => buf.iter_mut().for_each(|sample| {
+                *sample = *sample + lpf(*sample) * shelf_gain;
+            }),
+            Mode::HIGHSHELF => buf.iter_mut().for_each(|sample| {
+                *sample = *sample + (*sample - lpf(*sample)) * shelf_gain;
+            }),
+            Mode::PEAK | Mode::BANDPASS | Mode::NOTCH => {}
+        }
+
+        self.z1 = z1;
+    }
 }
 
-pub struct TPTOnePoleStereo {
-    left: TPTOnePole,
-    right: TPTOnePole,
+#[derive(Clone)]
+pub struct TPTOnePoleStereo<T> {
+    left: [TPTOnePole<T>; MAX_STAGES],
+    right: [TPTOnePole<T>; MAX_STAGES],
+    stages: usize,
 }
 
-impl TPTOnePoleStereo {
+impl<T: Sample> TPTOnePoleStereo<T> {
     pub fn new(mode: Mode, sample_rate: f64, freq_hz: f64) -> Self {
+        Self::with_stages(mode, sample_rate, freq_hz, 1)
+    }
+
+    // Cascades `stages` identical one-pole filters, each adding 6 dB/oct to the slope.
+    pub fn with_stages(mode: Mode, sample_rate: f64, freq_hz: f64, stages: usize) -> Self {
+        Self::from_stage(TPTOnePole::new(mode, sample_rate, freq_hz), stages)
+    }
+
+    // A single stage shelving filter, boosting or cutting by `gain_db`.
+    pub fn shelf(mode: Mode, sample_rate: f64, freq_hz: f64, gain_db: f64) -> Self {
+        Self::from_stage(
+            TPTOnePole::with_gain(mode, sample_rate, freq_hz, gain_db),
+            1,
+        )
+    }
+
+    fn from_stage(stage: TPTOnePole<T>, stages: usize) -> Self {
         Self {
-            left: TPTOnePole::new(mode.clone(), sample_rate, freq_hz),
-            right: TPTOnePole::new(mode.clone(), sample_rate, freq_hz),
+            left: core::array::from_fn(|_| stage.clone()),
+            right: core::array::from_fn(|_| stage.clone()),
+            stages: stages.clamp(1, MAX_STAGES),
         }
     }
 
-    pub fn process(&mut self, input: (f32, f32)) -> (f32, f32) {
-        (self.left.process(input.0), self.right.process(input.1))
+    pub fn reset(&mut self) {
+        for stage in self.left.iter_mut().chain(self.right.iter_mut()) {
+            stage.reset();
+        }
+    }
+
+    // Moves the left and right cutoffs, which can differ to decorrelate the channels.
+    pub fn set_cutoffs(&mut self, sample_rate: f64, left_hz: f64, right_hz: f64) {
+        for stage in &mut self.left {
+            stage.set_cutoff(sample_rate, left_hz);
+        }
+        for stage in &mut self.right {
+            stage.set_cutoff(sample_rate, right_hz);
+        }
+    }
+
+    // Changes the linear gain of the shelving modes, without clearing the filter state.
+    pub fn set_shelf_gain(&mut self, shelf_gain: f64) {
+        for stage in self.left.iter_mut().chain(self.right.iter_mut()) {
+            stage.shelf_gain = shelf_gain;
+        }
+    }
+
+    pub fn magnitude_response(&self, freq_hz: f64, sample_rate: f64) -> f64 {
+        // Every stage, and both channels, share the same coefficient.
+        let stage = self.left[0].magnitude_response(freq_hz, sample_rate);
+
+        (0..self.stages).fold(1.0, |gain, _| gain * stage)
+    }
+
+    pub fn process(&mut self, input: Frame<T>) -> Frame<T> {
+        let stages = self.stages;
+        let l = self.left[..stages]
+            .iter_mut()
+            .fold(input.l, |sample, stage| stage.process(sample));
+        let r = self.right[..stages]
+            .iter_mut()
+            .fold(input.r, |sample, stage| stage.process(sample));
+
+        Frame::new(l, r)
+    }
+
+    pub fn process_block(&mut self, l: &mut [T], r: &mut [T]) {
+        for stage in &mut self.left[..self.stages] {
+            stage.process_block(l);
+        }
+        for stage in &mut self.right[..self.stages] {
+            stage.process_block(r);
+        }
     }
 }
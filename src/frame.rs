@@ -0,0 +1,86 @@
+use crate::Sample;
+use core::ops::{Add, Mul, Neg, Sub};
+
+/// `Frame` is a single stereo sample, holding one value for each channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Frame<T = f32> {
+    /// The left channel.
+    pub l: T,
+
+    /// The right channel.
+    pub r: T,
+}
+
+impl<T: Sample> Frame<T> {
+    /// Creates a new `Frame` from its left and right channels.
+    pub const fn new(l: T, r: T) -> Self {
+        Self { l, r }
+    }
+
+    /// Creates a new `Frame` with the same value in both channels.
+    pub const fn mono(value: T) -> Self {
+        Self { l: value, r: value }
+    }
+
+    /// Applies a function to both channels of the frame.
+    pub fn map(self, f: impl Fn(T) -> T) -> Self {
+        Self::new(f(self.l), f(self.r))
+    }
+
+    /// Returns the frame with its left and right channels swapped.
+    pub fn swap(self) -> Self {
+        Self {
+            l: self.r,
+            r: self.l,
+        }
+    }
+
+    // Converts the frame to `f32`, for metering and envelope following.
+    pub(crate) fn to_f32(self) -> Frame {
+        Frame::new(self.l.to_f32(), self.r.to_f32())
+    }
+}
+
+impl<T: Sample> Add for Frame<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.l + rhs.l, self.r + rhs.r)
+    }
+}
+
+impl<T: Sample> Sub for Frame<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.l - rhs.l, self.r - rhs.r)
+    }
+}
+
+impl<T: Sample> Mul<T> for Frame<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Self::new(self.l * rhs, self.r * rhs)
+    }
+}
+
+impl<T: Sample> Neg for Frame<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.l, -self.r)
+    }
+}
+
+impl<T> From<(T, T)> for Frame<T> {
+    fn from((l, r): (T, T)) -> Self {
+        Self { l, r }
+    }
+}
+
+impl<T> From<Frame<T>> for (T, T) {
+    fn from(frame: Frame<T>) -> Self {
+        (frame.l, frame.r)
+    }
+}
@@ -0,0 +1,163 @@
+use crate::math::{cosf, powf};
+use crate::util::noise;
+use crate::{Frame, Sample};
+use core::f32::consts::TAU;
+
+// The most grains that can play at once.
+const MAX_GRAINS: usize = 8;
+
+// The shortest and longest grains, in milliseconds.
+const MIN_GRAIN_MS: f32 = 5.0;
+const MAX_GRAIN_MS: f32 = 500.0;
+
+// The widest pitch spread, in semitones either side of the original pitch.
+const MAX_PITCH_SPREAD: f32 = 12.0;
+
+// The seed of the grain positions and pitches, so renders are repeatable.
+const GRAIN_SEED: u32 = 0x2545_f491;
+
+/// `GranularParams` are the parameters of the granular delay mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GranularParams {
+    /// How long each grain plays for, in milliseconds, between 5 and 500 ms.
+    pub grain_size_ms: f32,
+
+    /// How many grains overlap at once, on average, between 1.0 and 8.0.
+    pub grain_density: f32,
+
+    /// How far each grain's pitch is randomly shifted, in semitones either side
+    /// of the original pitch, up to 12.
+    pub pitch_spread: f32,
+
+    /// How far each grain's read position is randomly moved from the delay time,
+    /// in milliseconds either side.
+    pub position_spread: f32,
+}
+
+impl GranularParams {
+    /// Creates new `GranularParams` from a grain size and density, without any
+    /// random pitch or position.
+    pub fn new(grain_size_ms: f32, grain_density: f32) -> Self {
+        Self {
+            grain_size_ms,
+            grain_density,
+            pitch_spread: 0.0,
+            position_spread: 0.0,
+        }
+    }
+
+    // The furthest a grain can read beyond the delay time, in milliseconds.
+    pub(crate) fn max_reach_ms(&self) -> f32 {
+        self.position_spread.abs() + self.grain_size_ms.clamp(MIN_GRAIN_MS, MAX_GRAIN_MS) / 2.0
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Grain {
+    // How far through the grain it is, and how long it lasts, in samples.
+    age: f32,
+    len: f32,
+    // The grain's offset from the delay time, in samples, and its pitch ratio.
+    offset: f32,
+    rate: f32,
+}
+
+impl Grain {
+    fn is_playing(&self) -> bool {
+        self.age < self.len
+    }
+}
+
+// Plays short, overlapping grains read from random positions and pitches in the
+// delay buffer, instead of one coherent read.
+#[derive(Clone)]
+pub(crate) struct Granular {
+    grains: [Grain; MAX_GRAINS],
+    // How many samples until the next grain starts.
+    countdown: f32,
+    seed: u32,
+}
+
+impl Granular {
+    pub fn new() -> Self {
+        Self {
+            grains: [Grain::default(); MAX_GRAINS],
+            countdown: 0.0,
+            seed: GRAIN_SEED,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    // Starts a new grain in a free slot, if there is one.
+    fn spawn(&mut self, params: &GranularParams, len: f32, sample_rate: f32) {
+        let pitch_spread = params.pitch_spread.clamp(0.0, MAX_PITCH_SPREAD);
+        let rate = powf(2.0, noise(&mut self.seed) * pitch_spread / 12.0);
+        let offset = noise(&mut self.seed) * params.position_spread.abs() / 1000.0 * sample_rate;
+
+        if let Some(grain) = self.grains.iter_mut().find(|grain| !grain.is_playing()) {
+            *grain = Grain {
+                age: 0.0,
+                len,
+                offset,
+                rate,
+            };
+        }
+    }
+
+    // Sums the playing grains, read from `buffer` around the left and right
+    // delays, in samples. `index` is where the next sample will be written.
+    pub fn process<T: Sample>(
+        &mut self,
+        buffer: &[Frame<T>],
+        index: usize,
+        params: GranularParams,
+        (delay_left, delay_right): (f32, f32),
+        sample_rate: f32,
+    ) -> Frame<T> {
+        let len = params.grain_size_ms.clamp(MIN_GRAIN_MS, MAX_GRAIN_MS) / 1000.0 * sample_rate;
+        let density = params.grain_density.clamp(1.0, MAX_GRAINS as f32);
+
+        // Start grains at a steady rate, so on average `density` of them overlap.
+        self.countdown -= 1.0;
+        if self.countdown <= 0.0 {
+            self.spawn(&params, len, sample_rate);
+            self.countdown += len / density;
+        }
+
+        let read = |delay: f32, channel: fn(Frame<T>) -> T| {
+            let buffer_len = buffer.len();
+            let delay = delay.clamp(1.0, (buffer_len - 1).max(1) as f32);
+            let whole = delay as usize;
+            let fraction = T::from_f32(delay - whole as f32);
+            let tap = |delay: usize| {
+                channel(buffer[(index + buffer_len - delay.min(buffer_len)) % buffer_len])
+            };
+
+            tap(whole) * (T::ONE - fraction) + tap(whole + 1) * fraction
+        };
+
+        let mut sum = Frame::default();
+        for grain in self.grains.iter_mut().filter(|grain| grain.is_playing()) {
+            // The read head moves at the grain's pitch, so its delay drifts,
+            // passing through the grain's position halfway through.
+            let drift = (grain.age - grain.len / 2.0) * (1.0 - grain.rate);
+            let window = 0.5 - 0.5 * cosf(TAU * grain.age / grain.len);
+            let window = T::from_f32(window);
+
+            sum = sum
+                + Frame::new(
+                    read(delay_left + grain.offset + drift, |frame| frame.l),
+                    read(delay_right + grain.offset + drift, |frame| frame.r),
+                ) * window;
+
+            grain.age += 1.0;
+        }
+
+        // Overlapping Hann windows sum to half the density, so scale that back to unity.
+        sum * T::from_f32((2.0 / density).min(1.0))
+    }
+}
@@ -0,0 +1,64 @@
+use crate::math::{floorf, sinf, truncf};
+use core::f32::consts::TAU;
+
+/// `ModWaveform` is the shape of the LFO that modulates the delay time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModWaveform {
+    /// A smooth sine wave.
+    Sine,
+
+    /// A triangle wave, which sweeps the delay time at a constant speed.
+    Triangle,
+}
+
+/// `ModTarget` is the parameter an external control signal modulates, with
+/// [`Delay::process_modulated`].
+///
+/// [`Delay::process_modulated`]: crate::Delay::process_modulated
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModTarget {
+    /// Moves both delay times, by `depth` milliseconds at full control.
+    DelayTime,
+
+    /// Moves the lowpass cutoff, by `depth` octaves at full control.
+    Cutoff,
+
+    /// Adds `depth` to the feedback at full control, keeping it below 1.0.
+    Feedback,
+
+    /// Adds `depth` to the dry/wet mix at full control, keeping it between 0.0 and 1.0.
+    Mix,
+}
+
+// A low frequency oscillator, producing values between -1.0 and 1.0.
+#[derive(Clone)]
+pub(crate) struct Lfo {
+    phase: f32,
+}
+
+impl Lfo {
+    pub fn new() -> Self {
+        Self { phase: 0.0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    pub fn next(&mut self, rate_hz: f32, sample_rate: f32, waveform: ModWaveform) -> f32 {
+        let value = match waveform {
+            ModWaveform::Sine => sinf(self.phase * TAU),
+            ModWaveform::Triangle => {
+                let phase = self.phase + 0.25;
+                1.0 - 4.0 * (phase - truncf(phase) - 0.5).abs()
+            }
+        };
+
+        let phase = self.phase + rate_hz / sample_rate;
+        self.phase = phase - floorf(phase);
+
+        value
+    }
+}
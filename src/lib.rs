@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
 //! A delay effect modelled after the H-Delay by Waves.
@@ -13,179 +14,3050 @@
 //!   ..Settings::default()
 //! });
 //!
-//! let input = vec![...];
+//! let input = vec![0.0; 512];
 //! let mut output = vec![0.0; 512];
 //!
-//! delay.process(&mut input, &mut output);
+//! delay.process(&input, &mut output);
 //! ```
 //!
+//! The crate is `no_std` compatible. Disable the default `std` feature and enable
+//! `libm` to build it for targets without an OS. It still needs an allocator
+//! for the delay buffer.
 
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("aych-delay needs either the `std` or the `libm` feature enabled");
+
+extern crate alloc;
+
+mod builder;
+mod chorus;
+mod crush;
+mod envelope;
 mod filters;
-use filters::{Mode, TPTOnePoleStereo};
+mod frame;
+mod granular;
+mod lfo;
+mod math;
+mod meter;
+mod oversample;
+mod sample;
+mod saturation;
+mod smoother;
+mod stereo;
+mod tap;
+mod tempo;
+mod timechange;
+mod util;
+#[cfg(feature = "wav")]
+pub mod wav;
+mod wow;
+use chorus::Chorus;
+use crush::{quantize, Decimator};
+use filters::{
+    diffuser, BiquadStereo, Diffuser, Mode, StateVariableStereo, StereoFilter, TPTOnePoleStereo,
+};
+use granular::Granular;
+use tap::TapFilter;
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+pub use filters::{BandParams, FilterRouting, FilterSlope, FilterType, PeakParams};
+use lfo::Lfo;
+use math::{ceilf, cosf, lnf, pow, powf, sinf};
+use meter::Meter;
+use oversample::Oversampler;
+use smoother::Smoother;
+use stereo::{mid_side_width, Haas};
+use timechange::DelayTime;
+use util::{db_to_gain, flush_denormal, gain_to_db};
+use wow::WowFlutter;
+
+pub use builder::{SettingsBuilder, SettingsError};
+pub use chorus::ChorusParams;
+pub use envelope::EnvelopeFollower;
+pub use frame::Frame;
+pub use granular::GranularParams;
+pub use lfo::{ModTarget, ModWaveform};
+pub use sample::Sample;
+pub use saturation::Saturation;
+pub use stereo::{PhaseReverse, StereoMode};
+pub use tap::Tap;
+pub use tempo::{NoteDivision, TapTempo};
+pub use timechange::TimeChangeMode;
+
+const DEFAULT_SAMPLE_RATE: f32 = 44_100.0;
+
+/// The highest feedback level the delay will apply. Anything at or above 1.0
+/// makes the repeats grow without bound.
+pub const MAX_FEEDBACK: f32 = 0.99;
+
+// The length of the fades at the edges of each reverse window, in milliseconds.
+const REVERSE_FADE_MS: f32 = 5.0;
+
+// The corner frequency of the shelf that brightens each repeat.
+const REPEAT_BRIGHTEN_FREQ: f64 = 3000.0;
 
-const SAMPLE_RATE: f32 = 44_100.0;
+// The cutoff frequency of the DC blocker in the feedback path.
+const DC_BLOCK_FREQ: f64 = 10.0;
+
+// How quickly the filter envelope opens the lowpass filter on a loud input, in milliseconds.
+const FILTER_ENV_ATTACK_MS: f32 = 5.0;
+
+// The lowest pitch the resonator can be tuned to, in Hz, so its period stays finite.
+const MIN_TUNE_HZ: f32 = 1.0;
 
 /// `Settings` contains the parameters for the delay effect.
+///
+/// With the `serde` feature enabled, settings can be saved and loaded as presets.
+/// Missing fields fall back to their defaults and unknown fields are ignored, so
+/// presets keep loading across versions.
+///
+/// Settings can be cloned and compared, so a snapshot of `delay.settings` can be
+/// kept and restored later, or checked for changes.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct Settings {
+    /// The sample rate of the audio being processed, in Hz.
+    pub sample_rate: f32,
+
     /// The delay time in milliseconds.
+    ///
+    /// Anything from one sample up is usable, so sub-millisecond times in the
+    /// flanger range work too, with [`Interpolation::Linear`] to sweep them
+    /// smoothly. Shorter times are held at one sample, or at the processing
+    /// latency while the saturation is oversampled or the repeats are diffused,
+    /// when the dry signal is delayed to match, as reported by [`Delay::latency_samples`]. The delay buffer holds at
+    /// most a minute, so longer times read from a minute back.
     pub delay_time: f32,
 
+    /// An optional, independent delay time for the right channel, in milliseconds.
+    /// `None` uses `delay_time` for both channels.
+    pub delay_time_right: Option<f32>,
+
+    /// A small extra delay on one channel, in milliseconds, to widen the repeats.
+    /// Positive values delay the right channel relative to the left, and negative
+    /// values delay the left relative to the right.
+    pub stereo_offset_ms: f32,
+
+    /// Whether to tune the delay to a pitch, turning it into a comb filter resonator.
+    /// The delay time is set to one period of `tune_hz`, and is read with linear
+    /// interpolation so the pitch is exact.
+    pub resonator: bool,
+
+    /// The pitch of the resonator, in Hz, when `resonator` is enabled. Pitches
+    /// below 1 Hz are raised to 1 Hz. `phase_reverse` is ignored while the
+    /// resonator is on, since inverting every pass would ring an octave low.
+    pub tune_hz: f32,
+
+    /// How the delay moves to a new delay time, when it changes while running.
+    pub time_change_mode: TimeChangeMode,
+
+    /// The gain applied to the input before it enters the delay line, to drive the
+    /// repeats harder into the saturation. It only affects the wet path, so the
+    /// dry signal stays untouched. 1.0 is unity gain.
+    pub input_gain: f32,
+
     /// The output level of the delay effect. 1.0 is unity gain.
+    /// See [`Settings::output_level_from_db`] to set it in decibels.
     pub output_level: f32,
 
+    /// The placement of the whole output, dry and wet, between -1.0 (full left)
+    /// and 1.0 (full right). It's a constant-power pan, with unity gain at 0.0, so
+    /// the nearer channel rises by up to 3 dB as the other one falls away.
+    pub balance: f32,
+
     /// The feedback level of the delay effect (Also known as "decay").
-    /// 0.0 is no feedback, 1.0 is infinite feedback.
+    /// 0.0 is no feedback, and values are clamped to [`MAX_FEEDBACK`].
     pub feedback: f32,
 
-    /// Whether to use ping-pong delay.
+    /// How long the repeats take to fade by 60 dB, in seconds. When it's set,
+    /// the feedback follows it, recomputed from the delay time, and `feedback`
+    /// is ignored. For a number of repeats, use that many delay times.
+    pub decay_time: Option<f32>,
+
+    /// Whether to use ping-pong delay. Switching crossfades between the two
+    /// routings over `smoothing_time_ms`, so it doesn't click.
     pub ping_pong: bool,
 
-    /// The width of the delay effect, when ping-pong is enabled.
-    /// 0.0 is mono, 1.0 is full stereo.
+    /// How many times the repeats bounce before they stop, whatever the feedback.
+    /// The count starts once the input goes quiet, and the feedback is cut before
+    /// the repeat after the last one. Each repeat is one bounce between the channels when
+    /// ping-pong is enabled. `None` lets the repeats decay with the feedback alone.
+    pub ping_pong_bounces: Option<u32>,
+
+    /// How late every other repeat lands, from 0.0 (straight) to 1.0, as a
+    /// fraction of half the delay time. 0.66 is a triplet shuffle, and suits
+    /// eighth or sixteenth note delays. Swing needs `ping_pong`, as it lengthens
+    /// the right channel's delay time and shortens the left's by the same amount,
+    /// so the repeats still land on the beat in pairs.
+    pub swing: f32,
+
+    /// How the stereo width is controlled. See [`StereoMode`].
+    pub stereo_mode: StereoMode,
+
+    /// The width of the delay effect, when ping-pong is enabled and `stereo_mode`
+    /// is [`StereoMode::PingPong`], between 0.0 and 1.0.
+    /// At 1.0 the repeats bounce fully between the channels. At 0.0 the input and
+    /// the repeats are summed to mono, so both channels are identical. Values in
+    /// between crossfade linearly from the mono sum to the full ping-pong.
     pub width: f32,
 
-    /// Whether to reverse the phase of the delayed signal.
-    pub phase_reverse: bool,
+    /// The width of the wet signal when `stereo_mode` is [`StereoMode::MidSide`].
+    /// 1.0 leaves it untouched, 0.0 collapses it to mono, and values above 1.0
+    /// widen it beyond the original stereo image.
+    pub ms_width: f32,
+
+    /// How much of each channel's repeats bleed into the opposite channel's feedback,
+    /// independently of ping-pong. 0.0 keeps the channels separate, 1.0 swaps them fully.
+    pub cross_feedback: f32,
+
+    /// Which channels of the delayed signal have their phase reversed. Reversing
+    /// just one channel gives a wide, hollow stereo image.
+    pub phase_reverse: PhaseReverse,
 
     /// The cutoff frequency of the lowpass filter.
     pub lowpass_filter: f64,
 
+    /// The cutoff frequency of the right channel's lowpass filter, if it differs
+    /// from the left's. Slightly different cutoffs decorrelate the repeats.
+    /// `None` uses `lowpass_filter` for both channels.
+    pub lowpass_filter_right: Option<f64>,
+
     /// The cutoff frequency of the highpass filter.
     pub highpass_filter: f64,
 
+    /// The cutoff frequency of the right channel's highpass filter, if it differs
+    /// from the left's. `None` uses `highpass_filter` for both channels.
+    pub highpass_filter_right: Option<f64>,
+
+    /// Whether the lowpass filter is applied. Disabling it leaves the top end
+    /// of the repeats untouched, rather than rolling off gently above the cutoff.
+    pub lowpass_enabled: bool,
+
+    /// Whether the highpass filter is applied. Disabling it leaves the low end
+    /// of the repeats untouched. With either filter disabled, the repeats go
+    /// through the other one alone, whatever the `filter_routing`.
+    pub highpass_enabled: bool,
+
+    /// How steeply the lowpass and highpass filters roll off.
+    pub filter_slope: FilterSlope,
+
+    /// How the lowpass and highpass filters are chained.
+    pub filter_routing: FilterRouting,
+
+    /// Whether the lowpass and highpass filters are in the feedback path, so
+    /// each repeat is darker than the last. Otherwise they filter the wet signal
+    /// once, on its way out, and every repeat keeps the same tone.
+    pub filter_in_feedback: bool,
+
+    /// Whether the dry signal also runs through the lowpass and highpass
+    /// filters, band-limiting the whole output for a lo-fi or telephone sound.
+    /// The dry signal has its own copies of the filters.
+    pub filter_dry: bool,
+
+    /// The filter topology used for the lowpass and highpass filters.
+    pub filter_type: FilterType,
+
+    /// The resonance (Q) of the lowpass and highpass filters, when `filter_type`
+    /// is [`FilterType::Svf`]. 0.707 is flat, higher values add a peak at the cutoff.
+    pub resonance: f64,
+
+    /// How far the input's envelope opens the lowpass filter, in octaves above
+    /// `lowpass_filter` for a full-scale input. Loud hits brighten the repeats,
+    /// which darken again as the input decays. 0.0 disables the envelope.
+    pub filter_env_amount: f32,
+
+    /// How long the filter envelope takes to fall back once the input gets
+    /// quieter, in milliseconds.
+    pub filter_env_rate: f32,
+
+    /// The tilt of the tone of the repeats, in decibels. Positive values brighten the
+    /// repeats, by boosting above `shelf_freq` and cutting below it by half as much
+    /// each, and negative values warm them up. 0.0 disables the shelving filters.
+    pub shelf_gain_db: f32,
+
+    /// The frequency that `shelf_gain_db` tilts the repeats around, in Hz.
+    pub shelf_freq: f64,
+
+    /// How much each repeat is brightened by a high shelf in the feedback path, in
+    /// decibels, so the repeats shimmer as they build up rather than darkening.
+    /// The boost is capped so the treble in the loop never rises above
+    /// `MAX_FEEDBACK`, however high the feedback. 0.0 disables it.
+    pub repeat_brighten_db: f32,
+
+    /// An optional peaking (bell) EQ in the feedback path, to boost or cut a band
+    /// of the repeats. `None` disables it.
+    pub peak_filter: Option<PeakParams>,
+
+    /// An optional bandpass or notch filter in the feedback path, to narrow the
+    /// repeats down to a band, or to cut one out of them. `None` disables it.
+    pub band_filter: Option<BandParams>,
+
+    /// The corner frequency of an optional allpass filter in the feedback path.
+    /// `None` disables the allpass stage.
+    pub allpass_filter: Option<f64>,
+
+    /// How much the repeats are smeared by a chain of allpass filters in the feedback
+    /// path, between 0.0 and 1.0. Higher values blur each echo into a cloud that
+    /// builds up over the repeats. The diffuser delays the repeats by about 12 ms,
+    /// which they're read early to make up for, like the oversampling latency.
+    /// Switching it on or off from 0.0 crossfades over `smoothing_time_ms`.
+    /// 0.0 disables the diffuser.
+    pub diffusion: f32,
+
     /// The dry/wet mix of the delay effect.
     pub dry_wet_mix: f32,
+
+    /// Whether to output only the wet signal, regardless of `dry_wet_mix`.
+    /// Useful on a send/return bus, where the dry signal is already present.
+    /// Switching is smoothed over `smoothing_time_ms`.
+    pub wet_only: bool,
+
+    /// How the dry and wet signals are crossfaded by `dry_wet_mix`.
+    pub mix_law: MixLaw,
+
+    /// An optional multi-voice chorus on the dry signal, to thicken it. It runs
+    /// alongside the delay, which is still fed the unchorused input. `None`
+    /// disables it.
+    pub chorus: Option<ChorusParams>,
+
+    /// How far to delay one channel of the dry signal, in milliseconds, up to
+    /// 30 ms. The Haas effect widens the dry signal to sit with a wide wet one.
+    /// Positive values delay the right channel, negative values the left, and
+    /// 0.0 disables it.
+    pub dry_haas_ms: f32,
+
+    /// How to read delay times that fall between two samples.
+    pub interpolation: Interpolation,
+
+    /// The rate of the LFO that modulates the delay time, in Hz.
+    pub mod_rate_hz: f32,
+
+    /// How far the LFO sweeps the delay time either side of `delay_time`, in milliseconds.
+    /// 0.0 disables modulation. Modulation is smoothest with [`Interpolation::Linear`].
+    pub mod_depth_ms: f32,
+
+    /// The shape of the LFO that modulates the delay time.
+    pub mod_waveform: ModWaveform,
+
+    /// How far the slow, tape-style "wow" wobbles the delay time, in milliseconds.
+    /// 0.0 disables it. Like `mod_depth_ms`, it's smoothest with [`Interpolation::Linear`].
+    pub wow_depth_ms: f32,
+
+    /// The rate of the wow, in Hz.
+    pub wow_rate_hz: f32,
+
+    /// How far the fast, tape-style "flutter" wobbles the delay time, in milliseconds.
+    /// 0.0 disables it.
+    pub flutter_depth_ms: f32,
+
+    /// The rate of the flutter, in Hz.
+    pub flutter_rate_hz: f32,
+
+    /// Whether to play the delayed signal backwards. Each window of one delay time is
+    /// captured and then played back reversed, so the repeats arrive a window late.
+    /// Switching crossfades over `smoothing_time_ms`, so it doesn't click.
+    pub reverse: bool,
+
+    /// Reads the repeats as short grains from random positions and pitches
+    /// around the delay time, rather than as one coherent read, for textures
+    /// that smear and shimmer as they feed back. `None` disables it.
+    pub granular: Option<GranularParams>,
+
+    /// Extra taps that read the delay buffer at their own times. When any are set,
+    /// the wet signal is the sum of the taps rather than the single repeating delay.
+    pub taps: Vec<Tap>,
+
+    /// The saturation applied to the repeats inside the feedback loop, so each
+    /// repeat is warmed up a little more than the last.
+    pub saturation: Saturation,
+
+    /// How hard the repeats are pushed into the saturation. 1.0 is unity.
+    pub drive: f32,
+
+    /// How many times over the saturation is oversampled, to stop the harmonics it
+    /// adds from aliasing. 1 disables oversampling, and 2 and 4 are supported. The
+    /// oversampling filters add 16 samples of delay to the feedback path, which is
+    /// taken off the delay time to keep the repeats in time.
+    pub oversample: u32,
+
+    /// The bit depth the repeats are reduced to on every pass through the feedback
+    /// path, so they get grungier as they decay. It's clamped to between 1 and 24
+    /// bits. `None` keeps the full resolution.
+    pub bit_depth: Option<u8>,
+
+    /// How many samples each repeat is held for, reducing its sample rate on every
+    /// pass through the feedback path. `None` keeps the full sample rate.
+    pub downsample_factor: Option<u32>,
+
+    /// Whether to turn the wet signal down as the feedback goes up, so the summed
+    /// repeats don't clip. The wet signal is scaled by `(1 - feedback) / feedback`,
+    /// the inverse of the loop's steady-state gain. It's capped at unity, so it
+    /// only takes effect once `feedback` is above 0.5.
+    pub auto_gain: bool,
+
+    /// Whether to remove DC offset from the repeats, so it can't build up in the feedback loop.
+    pub dc_block: bool,
+
+    /// Whether to freeze the delay, looping the current buffer contents indefinitely
+    /// without taking in new input.
+    pub freeze: bool,
+
+    /// How much the wet signal is ducked while the input is loud, so the repeats
+    /// bloom in the gaps. 0.0 disables ducking, 1.0 fully ducks a full-scale input.
+    pub duck_amount: f32,
+
+    /// How quickly the ducking reacts to the input getting louder, in milliseconds.
+    pub duck_attack_ms: f32,
+
+    /// How quickly the ducking lets go once the input gets quieter, in milliseconds.
+    pub duck_release_ms: f32,
+
+    /// Whether to bypass the effect, passing the input through untouched. The delay
+    /// keeps running while bypassed, so the tail picks up seamlessly when re-enabled.
+    /// Switching crossfades over `smoothing_time_ms`, so it doesn't click.
+    pub bypass: bool,
+
+    /// How long `feedback`, `dry_wet_mix`, `input_gain` and `output_level` take to
+    /// ramp to a new value, in milliseconds. This avoids zipper noise when they
+    /// are automated.
+    pub smoothing_time_ms: f32,
+
+    /// The window over which the wet signal's RMS level is measured, in milliseconds.
+    pub meter_rms_window_ms: f32,
+
+    /// How long the wet signal's peak level takes to fall back, in milliseconds.
+    pub meter_release_ms: f32,
+}
+
+/// `Interpolation` controls how the delay buffer is read at fractional delay times.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    /// Round the delay time down to the nearest whole sample.
+    None,
+
+    /// Linearly interpolate between the two neighbouring samples.
+    Linear,
+}
+
+/// `MixLaw` is the curve the dry/wet mix crossfades along.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixLaw {
+    /// Blend the dry and wet signals linearly. The level dips by 3dB halfway
+    /// through, when the two are uncorrelated.
+    Linear,
+
+    /// Crossfade along sine and cosine curves, keeping the total power, and so
+    /// the perceived level, constant across the mix.
+    EqualPower,
+}
+
+impl MixLaw {
+    // Returns the dry and wet gains for a dry/wet mix.
+    fn gains(self, mix: f32) -> (f32, f32) {
+        match self {
+            MixLaw::Linear => (1.0 - mix, mix),
+            MixLaw::EqualPower => {
+                let angle = mix.clamp(0.0, 1.0) * core::f32::consts::FRAC_PI_2;
+                (cosf(angle), sinf(angle))
+            }
+        }
+    }
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            sample_rate: DEFAULT_SAMPLE_RATE,
             delay_time: 250.,
+            delay_time_right: None,
+            stereo_offset_ms: 0.0,
+            resonator: false,
+            tune_hz: 220.0,
+            time_change_mode: TimeChangeMode::Hard,
+            input_gain: 1.0,
             output_level: 1.0,
+            balance: 0.0,
             feedback: 0.8,
+            decay_time: None,
             ping_pong: true,
+            ping_pong_bounces: None,
+            swing: 0.0,
+            stereo_mode: StereoMode::PingPong,
             width: 1.0,
-            phase_reverse: true,
+            ms_width: 1.0,
+            cross_feedback: 0.0,
+            phase_reverse: PhaseReverse::both(true),
             lowpass_filter: 5000.0,
+            lowpass_filter_right: None,
             highpass_filter: 500.0,
+            highpass_filter_right: None,
+            lowpass_enabled: true,
+            highpass_enabled: true,
+            filter_in_feedback: true,
+            filter_dry: false,
+            filter_slope: FilterSlope::Slope6,
+            filter_routing: FilterRouting::LpThenHp,
+            filter_type: FilterType::OnePole,
+            resonance: core::f64::consts::FRAC_1_SQRT_2,
+            filter_env_amount: 0.0,
+            filter_env_rate: 200.0,
+            shelf_gain_db: 0.0,
+            shelf_freq: 1000.0,
+            repeat_brighten_db: 0.0,
+            peak_filter: None,
+            band_filter: None,
+            allpass_filter: None,
+            diffusion: 0.0,
             dry_wet_mix: 0.5,
+            wet_only: false,
+            mix_law: MixLaw::Linear,
+            chorus: None,
+            dry_haas_ms: 0.0,
+            interpolation: Interpolation::None,
+            mod_rate_hz: 0.5,
+            mod_depth_ms: 0.0,
+            mod_waveform: ModWaveform::Sine,
+            wow_depth_ms: 0.0,
+            wow_rate_hz: 0.8,
+            flutter_depth_ms: 0.0,
+            flutter_rate_hz: 9.0,
+            reverse: false,
+            granular: None,
+            taps: Vec::new(),
+            saturation: Saturation::None,
+            drive: 1.0,
+            oversample: 1,
+            bit_depth: None,
+            downsample_factor: None,
+            auto_gain: false,
+            dc_block: true,
+            freeze: false,
+            duck_amount: 0.0,
+            duck_attack_ms: 10.0,
+            duck_release_ms: 250.0,
+            bypass: false,
+            smoothing_time_ms: 10.0,
+            meter_rms_window_ms: 300.0,
+            meter_release_ms: 1000.0,
+        }
+    }
+}
+
+impl Settings {
+    /// Returns a [`SettingsBuilder`], starting from the default settings.
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::new()
+    }
+
+    /// Checks that the settings are usable, describing the first problem found.
+    ///
+    /// This rejects a non-positive sample rate, negative delay times, a resonator
+    /// pitch below 1 Hz, feedback outside `[0.0, 1.0)`, a dry/wet mix outside
+    /// `[0.0, 1.0]`, and, when both
+    /// filters are enabled, a lowpass cutoff at or below the highpass cutoff,
+    /// which would make the repeats almost silent.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if !valid_sample_rate(self.sample_rate) {
+            return Err(SettingsError::InvalidSampleRate(self.sample_rate));
+        }
+
+        let (left, right) = self.delay_times();
+        for delay_time in [left, right] {
+            if !(delay_time.is_finite() && delay_time >= 0.0) {
+                return Err(SettingsError::InvalidDelayTime(delay_time));
+            }
+        }
+
+        if self.resonator && !(self.tune_hz.is_finite() && self.tune_hz >= MIN_TUNE_HZ) {
+            return Err(SettingsError::InvalidTune(self.tune_hz));
+        }
+
+        if !(0.0..1.0).contains(&self.feedback) {
+            return Err(SettingsError::FeedbackOutOfRange(self.feedback));
+        }
+
+        if !(0.0..=1.0).contains(&self.dry_wet_mix) {
+            return Err(SettingsError::DryWetMixOutOfRange(self.dry_wet_mix));
+        }
+
+        let (lowpass_left, lowpass_right) = self.lowpass_cutoffs();
+        let (highpass_left, highpass_right) = self.highpass_cutoffs();
+        if self.lowpass_enabled && self.highpass_enabled {
+            for (lowpass, highpass) in [
+                (lowpass_left, highpass_left),
+                (lowpass_right, highpass_right),
+            ] {
+                if lowpass <= highpass {
+                    return Err(SettingsError::InvertedFilters { lowpass, highpass });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the delay time, in milliseconds, of a note division at the given tempo.
+    ///
+    /// ```rust
+    /// use aych_delay::{NoteDivision, Settings};
+    ///
+    /// assert_eq!(Settings::delay_time_from_tempo(120.0, NoteDivision::Eighth), 250.0);
+    /// ```
+    pub fn delay_time_from_tempo(bpm: f32, div: NoteDivision) -> f32 {
+        div.to_ms(bpm)
+    }
+
+    /// Converts an output level in decibels into the linear gain used by `output_level`.
+    ///
+    /// ```rust
+    /// use aych_delay::Settings;
+    ///
+    /// assert_eq!(Settings::output_level_from_db(0.0), 1.0);
+    /// assert_eq!(Settings::output_level_from_db(f32::NEG_INFINITY), 0.0);
+    /// ```
+    pub fn output_level_from_db(db: f32) -> f32 {
+        db_to_gain(db)
+    }
+
+    /// Converts a linear `output_level` into decibels. A level of 0.0 is `-inf` dB.
+    pub fn output_level_to_db(output_level: f32) -> f32 {
+        gain_to_db(output_level)
+    }
+
+    /// Converts a feedback level in decibels per repeat into a linear `feedback`.
+    pub fn feedback_from_db(db: f32) -> f32 {
+        db_to_gain(db)
+    }
+
+    /// Returns the `feedback` that makes repeats `delay_time_ms` apart fade by
+    /// 60 dB over `decay_time_s` seconds. A decay time of zero or less is no
+    /// feedback.
+    pub fn feedback_for_decay(decay_time_s: f32, delay_time_ms: f32) -> f32 {
+        match decay_time_s > 0.0 {
+            true => clamp_feedback(powf(TAIL_THRESHOLD, delay_time_ms / 1000.0 / decay_time_s)),
+            false => 0.0,
+        }
+    }
+
+    // Returns the effective feedback, following `decay_time` if it's set.
+    fn effective_feedback(&self) -> f32 {
+        match self.decay_time {
+            Some(decay_time_s) => {
+                let (left, right) = self.delay_times();
+                Self::feedback_for_decay(decay_time_s, left.max(right))
+            }
+            None => clamp_feedback(self.feedback),
+        }
+    }
+
+    // Returns the effective dry/wet mix, taking `wet_only` into account.
+    fn mix(&self) -> f32 {
+        match self.wet_only {
+            true => 1.0,
+            false => self.dry_wet_mix,
+        }
+    }
+
+    // Returns the left and right delay times, in milliseconds.
+    fn delay_times(&self) -> (f32, f32) {
+        if self.resonator {
+            let period = 1000.0 / self.tune_hz.max(MIN_TUNE_HZ);
+            return (period, period);
+        }
+
+        let offset = self.stereo_offset_ms;
+
+        (
+            self.delay_time + (-offset).max(0.0),
+            self.delay_time_right.unwrap_or(self.delay_time) + offset.max(0.0),
+        )
+    }
+
+    // Returns the left and right lowpass cutoffs, in Hz.
+    fn lowpass_cutoffs(&self) -> (f64, f64) {
+        (
+            self.lowpass_filter,
+            self.lowpass_filter_right.unwrap_or(self.lowpass_filter),
+        )
+    }
+
+    // Returns the left and right highpass cutoffs, in Hz.
+    fn highpass_cutoffs(&self) -> (f64, f64) {
+        (
+            self.highpass_filter,
+            self.highpass_filter_right.unwrap_or(self.highpass_filter),
+        )
+    }
+
+    // Returns how many samples oversampling the saturation delays the repeats by.
+    fn oversampling_latency(&self) -> f32 {
+        let oversampled = oversample::supported_factor(self.oversample) > 1
+            && self.saturation != Saturation::None;
+
+        match oversampled {
+            true => oversample::LATENCY as f32,
+            false => 0.0,
+        }
+    }
+
+    // Returns how many samples processing in the feedback path delays the left
+    // and right repeats by. The repeats are read that much early to make up for it.
+    fn processing_latency(&self) -> (f32, f32) {
+        let latency = self.oversampling_latency();
+
+        match self.diffusion > 0.0 {
+            true => {
+                let (left, right) = diffuser::latency(self.sample_rate);
+                (latency + left, latency + right)
+            }
+            false => (latency, latency),
+        }
+    }
+
+    // Returns how many frames the wet path lags the input by, beyond the delay
+    // time. Reading early can't make up the processing latency of a delay time
+    // shorter than it, since the shortest read is one sample.
+    fn wet_latency(&self) -> usize {
+        let (left, right) = self.delay_times();
+        let (latency_left, latency_right) = self.processing_latency();
+        let lag = |delay_time: f32, latency: f32| {
+            let delay = delay_samples(delay_time, self.sample_rate).max(1.0);
+            ceilf(latency + 1.0 - delay).max(0.0)
+        };
+
+        lag(left, latency_left).max(lag(right, latency_right)) as usize
+    }
+
+    // Returns the left and right delay times, with swing applied while ping-ponging.
+    // The repeats alternate between the channels, so the right channel's are late.
+    fn swung_delay_times(&self, ping_pong: bool) -> (f32, f32) {
+        let (left, right) = self.delay_times();
+        if !ping_pong || self.resonator {
+            return (left, right);
         }
+
+        let late = self.swing.clamp(0.0, 1.0) * self.delay_time / 2.0;
+
+        ((left - late).max(0.0), right + late)
+    }
+
+    // Returns the delay buffer length needed to hold the longest delay time.
+    fn delay_buffer_len(&self) -> usize {
+        let (left, right) = self.delay_times();
+        let (_, swung_right) = self.swung_delay_times(self.ping_pong);
+        let longest = self
+            .taps
+            .iter()
+            .map(|tap| tap.time_ms)
+            .fold(left.max(right).max(swung_right), f32::max)
+            + self.mod_depth_ms.abs()
+            + WowFlutter::max_depth_ms(self.wow_depth_ms, self.flutter_depth_ms)
+            + self.granular.map_or(0.0, |params| params.max_reach_ms());
+
+        // Reversing reads up to two windows back: the window being played
+        // backwards, and the one being captured.
+        let longest = match self.reverse {
+            true => longest * 2.0,
+            false => longest,
+        };
+
+        delay_buffer_len(longest, self.sample_rate)
     }
 }
 
-struct State {
-    delay_buffer: Vec<(f32, f32)>,
+#[derive(Clone)]
+struct State<T> {
+    delay_buffer: Vec<Frame<T>>,
     delay_buffer_index: usize,
-    lowpass_filter: TPTOnePoleStereo,
-    highpass_filter: TPTOnePoleStereo,
+    // How many of the oldest buffer samples are silence padded in by a resize,
+    // rather than audio that was written.
+    unfilled: usize,
+    delay_time: DelayTime,
+    lowpass_filter: StereoFilter<T>,
+    // The cutoffs the lowpass filter is currently set to, after the envelope.
+    lowpass_cutoff: (f64, f64),
+    filter_env: EnvelopeFollower,
+    highpass_filter: StereoFilter<T>,
+    // Copies of the lowpass and highpass filters, for the dry signal.
+    dry_filters: (StereoFilter<T>, StereoFilter<T>),
+    tilt_filter: Option<(TPTOnePoleStereo<T>, TPTOnePoleStereo<T>)>,
+    brighten_filter: TPTOnePoleStereo<T>,
+    peak_filter: Option<BiquadStereo<T>>,
+    band_filter: Option<StateVariableStereo<T>>,
+    allpass_filter: Option<TPTOnePoleStereo<T>>,
+    diffuser: Diffuser<T>,
+    oversampler: Oversampler<T>,
+    decimator: Decimator<T>,
+    dc_blocker: TPTOnePoleStereo<T>,
+    max_delay_ms: f32,
+    lfo: Lfo,
+    wow_flutter: WowFlutter,
+    reverse_position: (f32, f32),
+    feedback: Smoother,
+    dry_wet_mix: Smoother,
+    input_gain: Smoother,
+    output_level: Smoother,
+    freeze: Smoother,
+    // How far switched over to reading backwards, and to ping-pong, the delay is.
+    reverse: Smoother,
+    ping_pong: Smoother,
+    bypass: Smoother,
+    // How much the repeats are diffused, and how far switched over to the diffuser.
+    diffusion: Smoother,
+    diffused: Smoother,
+    // How many samples it's been since the input was last audible.
+    since_input: u32,
+    bounce_gate: Smoother,
+    // How many frames have had a NaN or infinity dropped from the feedback path.
+    nan_count: u64,
+    ducking: EnvelopeFollower,
+    chorus: Chorus<T>,
+    haas: Haas<T>,
+    granular: Granular,
+    tap_filters: Vec<TapFilter<T>>,
+    // Delays the dry signal by the wet latency, so the two stay in phase. It's
+    // allocated long enough for the most latency there can be.
+    dry_delay: Vec<Frame<T>>,
+    dry_delay_index: usize,
+    // The external control value for the current frame, and what it modulates.
+    control: Option<(ModTarget, f32)>,
+    meter: Meter,
 }
 
 /// `Delay` is the main struct for the delay effect.
 ///
 /// Internally, it maintains a buffer of delayed samples and a set of filters.
-pub struct Delay {
+/// Cloning a `Delay` copies all of that state, so the clone carries on from
+/// exactly the same point.
+///
+/// Audio is processed as `f32` by default. Any other [`Sample`] type, like `f64`,
+/// can be chosen with [`Delay::with_settings`], like `Delay::<f64>::with_settings`, and the whole signal path then runs
+/// at that precision.
+///
+/// # Offline rendering at `f64`
+///
+/// A `Delay<f64>` suits mastering-grade offline renders: the feedback loop
+/// accumulates in `f64`, so long, high-feedback tails build up less rounding
+/// error. It's a separate instance from any `f32` delay, so the real-time path
+/// is unaffected, and it renders the same signal within `f32` precision.
+///
+/// ```rust
+/// use aych_delay::{Delay, Settings};
+///
+/// let settings = Settings {
+///     feedback: 0.9,
+///     ..Settings::default()
+/// };
+/// let mut delay_f32 = Delay::new(settings.clone());
+/// let mut delay_f64 = Delay::<f64>::with_settings(settings);
+///
+/// let input: Vec<f64> = (0..8820).map(|i| ((i / 2) as f64 * 0.01).sin()).collect();
+/// let mut output_f64 = vec![0.0; input.len()];
+/// delay_f64.process(&input, &mut output_f64);
+///
+/// let input: Vec<f32> = input.iter().map(|&sample| sample as f32).collect();
+/// let mut output_f32 = vec![0.0; input.len()];
+/// delay_f32.process(&input, &mut output_f32);
+///
+/// for (a, b) in output_f32.iter().zip(&output_f64) {
+///     assert!((*a as f64 - b).abs() < 1e-3);
+/// }
+/// ```
+///
+/// # Changing settings while running
+///
+/// The settings are read every frame, but not every change is click-free:
+///
+/// - `feedback`, `decay_time`, `dry_wet_mix`, `wet_only`, `input_gain`,
+///   `output_level`, `ping_pong`, `reverse`, `freeze`, `bypass` and `diffusion` ramp or crossfade over
+///   `smoothing_time_ms`, so they're safe to change at any time.
+/// - Delay times follow `time_change_mode`, and only click with
+///   [`TimeChangeMode::Hard`]. Change them, and anything else that sets the
+///   buffer length, through the setters, like [`Delay::set_delay_time`], so the
+///   buffer is resized to fit.
+/// - The filter cutoffs move without clearing the filter state through
+///   [`Delay::set_lowpass`] and [`Delay::set_highpass`].
+/// - Anything else, like the saturation, the taps or the stereo mode, switches
+///   abruptly, and may click if it's changed while the repeats are ringing.
+/// - The filter type, slope and resonance, the shelving, peaking, band and
+///   allpass filters, and the sample rate are only read when the filters are built.
+///   Change them with [`Delay::set_settings`], which rebuilds the filters.
+#[derive(Clone)]
+pub struct Delay<T = f32> {
     /// The current settings for the delay effect.
+    ///
+    /// Changing the delay times or filters here leaves the internal state stale;
+    /// use [`Delay::set_settings`] or the setters instead.
     pub settings: Settings,
-    state: State,
+    state: State<T>,
+    // Extra delay lines for planar audio with other than two channels.
+    channels: Vec<State<T>>,
 }
 
-impl Delay {
-    /// Creates a new `Delay` instance with the specified settings.
-    pub fn new(settings: Settings) -> Self {
+/// `DelayState` is a snapshot of a [`Delay`]'s runtime state, taken with
+/// [`Delay::save_state`] and restored with [`Delay::load_state`].
+///
+/// It holds the settings along with everything that changes while processing:
+/// the delay buffer contents and position, the filter state, the modulation
+/// phases and the smoothers. Restoring it resumes processing exactly where the
+/// snapshot was taken.
+#[derive(Clone, Debug)]
+pub struct DelayState<T = f32> {
+    settings: Settings,
+    state: State<T>,
+    channels: Vec<State<T>>,
+}
+
+// The buffer contents are left out, since they'd swamp everything else.
+impl<T> fmt::Debug for State<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("delay_buffer_len", &self.delay_buffer.len())
+            .field("delay_buffer_index", &self.delay_buffer_index)
+            .field("max_delay_ms", &self.max_delay_ms)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Sample> Default for Delay<T> {
+    fn default() -> Self {
+        Self::with_settings(Settings::default())
+    }
+}
+
+impl<T: Sample> From<Settings> for Delay<T> {
+    fn from(settings: Settings) -> Self {
+        Self::with_settings(settings)
+    }
+}
+
+impl<T: Sample> From<&Settings> for Delay<T> {
+    fn from(settings: &Settings) -> Self {
+        Self::with_settings(settings.clone())
+    }
+}
+
+impl<T> fmt::Debug for Delay<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Delay")
+            .field("settings", &self.settings)
+            .field("state", &self.state)
+            .field("extra_channels", &self.channels.len())
+            .finish()
+    }
+}
+
+// Returns the most frames the wet path can lag the input by, at `sample_rate`.
+fn max_latency(sample_rate: f32) -> usize {
+    let (left, right) = diffuser::latency(sample_rate);
+
+    oversample::LATENCY + ceilf(left.max(right)) as usize
+}
+
+fn clamp_feedback(feedback: f32) -> f32 {
+    feedback.clamp(0.0, MAX_FEEDBACK)
+}
+
+// Whether the delay can run at `sample_rate`, which has to be a positive, finite
+// number of Hz.
+fn valid_sample_rate(sample_rate: f32) -> bool {
+    sample_rate.is_finite() && sample_rate > 0.0
+}
+
+// Steps through a reverse window of `window` samples, returning the delay to read
+// at and the gain of the fade that smooths over the window edges.
+fn reverse_window(position: &mut f32, window: f32, fade: f32) -> (f32, f32) {
+    let window = window.max(1.0);
+    let n = *position % window;
+    *position = n + 1.0;
+
+    // Reading from twice as far back as the window position plays the previous
+    // window backwards.
+    let delay = 2.0 * n + 1.0;
+
+    let fade = fade.min(window / 2.0).max(1.0);
+    let gain = (n / fade).min((window - n) / fade).min(1.0);
+
+    (delay, gain)
+}
+
+// Reads a stereo frame from a chunk of interleaved samples, with a silent
+// right channel if the chunk is cut short.
+fn interleaved_frame<T: Sample>(samples: &[T]) -> Frame<T> {
+    Frame::new(samples[0], samples.get(1).copied().unwrap_or(T::ZERO))
+}
+
+// Writes a stereo frame to a chunk of interleaved samples, dropping the right
+// channel if the chunk is cut short.
+fn write_interleaved<T: Sample>(samples: &mut [T], frame: Frame<T>) {
+    samples[0] = frame.l;
+    if let Some(right) = samples.get_mut(1) {
+        *right = frame.r;
+    }
+}
+
+// Converts a switch into a target for the smoother that crossfades it.
+fn switch_amount(on: bool) -> f32 {
+    match on {
+        true => 1.0,
+        false => 0.0,
+    }
+}
+
+// The level below which the input is considered silent, when counting bounces (-60dB).
+const BOUNCE_SILENCE: f32 = 0.001;
+
+// The level, relative to the input, at which a repeat is considered inaudible (-60dB).
+const TAIL_THRESHOLD: f32 = 0.001;
+
+// The longest tail that's reported, in milliseconds. Feedback close to unity
+// would otherwise take hours to decay.
+const MAX_TAIL_MS: f32 = 300_000.0;
+
+// Returns the gain of the shelf that brightens each repeat, if it's enabled. It's
+// capped so the treble in the loop never rises above `MAX_FEEDBACK`.
+fn brighten_gain(brighten_db: f32, feedback: f32) -> Option<f32> {
+    if brighten_db <= 0.0 {
+        return None;
+    }
+
+    let limit = match feedback > 0.0 {
+        true => (MAX_FEEDBACK / feedback).max(1.0),
+        false => f32::INFINITY,
+    };
+
+    Some(db_to_gain(brighten_db).min(limit))
+}
+
+// Converts a delay time in milliseconds into a (fractional) number of samples.
+fn delay_samples(delay_time: f32, sample_rate: f32) -> f32 {
+    (delay_time / 1000.0) * sample_rate
+}
+
+// The longest delay buffer that's allocated, in milliseconds, so a runaway or
+// infinite delay time can't exhaust memory.
+const MAX_BUFFER_MS: f32 = 60_000.0;
+
+// Converts a delay time in milliseconds into a delay buffer length in samples.
+fn delay_buffer_len(delay_time: f32, sample_rate: f32) -> usize {
+    // Times that aren't numbers cast to an empty buffer.
+    let delay_time = delay_time.clamp(0.0, MAX_BUFFER_MS);
+
+    // One extra sample gives the interpolated read a neighbour to blend with,
+    // and guarantees the buffer is never empty.
+    (delay_samples(delay_time, sample_rate) as usize).saturating_add(1)
+}
+
+// The most frames the dry filters are run over at once, by `process_block_planar`.
+const DRY_BLOCK: usize = 64;
+
+// The shortest delay buffer that's allocated, in milliseconds, so sweeping short
+// delays around the flanger range never reallocates.
+const MIN_BUFFER_MS: f32 = 20.0;
+
+// Returns the delay buffer length for the settings, preallocating at least `max_delay_ms`.
+fn buffer_len(settings: &Settings, max_delay_ms: f32) -> usize {
+    settings.delay_buffer_len().max(delay_buffer_len(
+        max_delay_ms.max(MIN_BUFFER_MS),
+        settings.sample_rate,
+    ))
+}
+
+impl<T: Sample> State<T> {
+    fn new(settings: &Settings) -> Self {
+        Self::with_max_delay(settings, 0.0)
+    }
+
+    // Creates the state with a delay buffer long enough for at least `max_delay_ms`.
+    fn with_max_delay(settings: &Settings, max_delay_ms: f32) -> Self {
+        let sample_rate = settings.sample_rate;
+
         // Initialize the delay buffer with the specified delay time.
-        let delay_buffer_size = (settings.delay_time / 1000.0) * SAMPLE_RATE;
+        let delay_buffer_size = buffer_len(settings, max_delay_ms);
 
-        let state = State {
-            delay_buffer: vec![(0.0, 0.0); delay_buffer_size as usize],
+        let lowpass_filter = Self::pass_filter(settings, Mode::LOWPASS, settings.lowpass_cutoffs());
+        let highpass_filter =
+            Self::pass_filter(settings, Mode::HIGHPASS, settings.highpass_cutoffs());
+
+        Self {
+            delay_buffer: vec![Frame::default(); delay_buffer_size],
             delay_buffer_index: 0,
-            lowpass_filter: TPTOnePoleStereo::new(
-                Mode::LOWPASS,
-                SAMPLE_RATE as f64,
-                settings.lowpass_filter,
+            unfilled: 0,
+            delay_time: DelayTime::new(settings.swung_delay_times(settings.ping_pong)),
+            dry_filters: (lowpass_filter.clone(), highpass_filter.clone()),
+            lowpass_filter,
+            lowpass_cutoff: settings.lowpass_cutoffs(),
+            filter_env: EnvelopeFollower::new(
+                FILTER_ENV_ATTACK_MS,
+                settings.filter_env_rate,
+                sample_rate,
             ),
-            highpass_filter: TPTOnePoleStereo::new(
-                Mode::HIGHPASS,
-                SAMPLE_RATE as f64,
-                settings.highpass_filter,
+            highpass_filter,
+            tilt_filter: Self::tilt_filter(settings),
+            brighten_filter: TPTOnePoleStereo::shelf(
+                Mode::HIGHSHELF,
+                sample_rate as f64,
+                REPEAT_BRIGHTEN_FREQ,
+                0.0,
             ),
-        };
-
-        Self { settings, state }
+            peak_filter: Self::peak_filter(settings),
+            band_filter: Self::band_filter(settings),
+            allpass_filter: Self::allpass_filter(settings),
+            diffuser: Diffuser::new(sample_rate),
+            oversampler: Oversampler::new(settings.oversample),
+            decimator: Decimator::new(),
+            dc_blocker: TPTOnePoleStereo::new(Mode::HIGHPASS, sample_rate as f64, DC_BLOCK_FREQ),
+            max_delay_ms,
+            lfo: Lfo::new(),
+            wow_flutter: WowFlutter::new(sample_rate),
+            reverse_position: (0.0, 0.0),
+            feedback: Smoother::new(
+                settings.effective_feedback(),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            dry_wet_mix: Smoother::new(settings.mix(), settings.smoothing_time_ms, sample_rate),
+            input_gain: Smoother::new(settings.input_gain, settings.smoothing_time_ms, sample_rate),
+            output_level: Smoother::new(
+                settings.output_level,
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            freeze: Smoother::new(
+                switch_amount(settings.freeze),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            reverse: Smoother::new(
+                switch_amount(settings.reverse),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            ping_pong: Smoother::new(
+                switch_amount(settings.ping_pong),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            bypass: Smoother::new(
+                switch_amount(settings.bypass),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            diffusion: Smoother::new(
+                settings.diffusion.clamp(0.0, 1.0),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            diffused: Smoother::new(
+                switch_amount(settings.diffusion > 0.0),
+                settings.smoothing_time_ms,
+                sample_rate,
+            ),
+            since_input: 0,
+            bounce_gate: Smoother::new(1.0, settings.smoothing_time_ms, sample_rate),
+            nan_count: 0,
+            ducking: EnvelopeFollower::new(
+                settings.duck_attack_ms,
+                settings.duck_release_ms,
+                sample_rate,
+            ),
+            chorus: Chorus::new(sample_rate),
+            haas: Haas::new(sample_rate),
+            granular: Granular::new(),
+            tap_filters: settings.taps.iter().map(|_| TapFilter::new()).collect(),
+            dry_delay: vec![Frame::default(); max_latency(sample_rate) + 1],
+            dry_delay_index: 0,
+            control: None,
+            meter: Meter::new(
+                settings.meter_rms_window_ms,
+                settings.meter_release_ms,
+                sample_rate,
+            ),
+        }
     }
 
-    /// Processes the input buffer and writes the updated signal to the output buffer.
-    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
-        let mut input_index = 0;
-        let mut output_index = 0;
-
-        // Convert the input buffer into an array of stereo samples.
-        let input_stereo: Vec<(f32, f32)> = input.chunks(2).map(|c| (c[0], c[1])).collect();
+    // Builds the lowpass or highpass filter in the feedback path.
+    fn pass_filter(settings: &Settings, mode: Mode, cutoffs: (f64, f64)) -> StereoFilter<T> {
+        let sample_rate = settings.sample_rate as f64;
+        let mut filter = StereoFilter::new(
+            settings.filter_type,
+            mode,
+            sample_rate,
+            cutoffs.0,
+            settings.filter_slope,
+            settings.resonance,
+        );
+        filter.set_cutoffs(sample_rate, cutoffs);
 
-        while input_index < input_stereo.len() && output_index < output.len() {
-            let input_sample = input_stereo[input_index];
-            let delay_sample = self.state.delay_buffer[self.state.delay_buffer_index];
+        filter
+    }
 
-            // Apply feedback by scaling the delay sample by the current feedback level.
-            let delay_sample = (
-                delay_sample.0 * self.settings.feedback,
-                delay_sample.1 * self.settings.feedback,
-            );
+    fn tilt_filter(settings: &Settings) -> Option<(TPTOnePoleStereo<T>, TPTOnePoleStereo<T>)> {
+        let sample_rate = settings.sample_rate as f64;
+        let gain_db = settings.shelf_gain_db as f64 / 2.0;
 
-            // Apply phase reverse by inverting the phase of the delay sample.
-            let delay_sample = match self.settings.phase_reverse {
-                true => (-delay_sample.0, -delay_sample.1),
-                false => delay_sample,
-            };
+        (settings.shelf_gain_db != 0.0).then(|| {
+            (
+                TPTOnePoleStereo::shelf(Mode::LOWSHELF, sample_rate, settings.shelf_freq, -gain_db),
+                TPTOnePoleStereo::shelf(Mode::HIGHSHELF, sample_rate, settings.shelf_freq, gain_db),
+            )
+        })
+    }
 
-            // Apply filtering by convolving the delay sample with the filter coefficients.
-            let delay_sample = self.state.lowpass_filter.process(delay_sample);
-            let delay_sample = self.state.highpass_filter.process(delay_sample);
+    fn peak_filter(settings: &Settings) -> Option<BiquadStereo<T>> {
+        settings.peak_filter.map(|peak| {
+            BiquadStereo::new(
+                Mode::PEAK,
+                settings.sample_rate as f64,
+                peak.freq_hz,
+                peak.q,
+                peak.gain_db,
+            )
+        })
+    }
 
-            // Apply ping-pong by mixing the left and right channels of the delay sample.
-            if self.settings.ping_pong {
-                let width = self.settings.width / 2.0 + 0.5;
+    fn band_filter(settings: &Settings) -> Option<StateVariableStereo<T>> {
+        settings.band_filter.map(|band| {
+            StateVariableStereo::new(
+                band.mode(),
+                settings.sample_rate as f64,
+                band.freq_hz,
+                band.q,
+            )
+        })
+    }
 
-                let pp_input = ((input_sample.0) * (1.0 - width), (input_sample.1) * width);
+    fn allpass_filter(settings: &Settings) -> Option<TPTOnePoleStereo<T>> {
+        settings.allpass_filter.map(|freq_hz| {
+            TPTOnePoleStereo::new(Mode::ALLPASS, settings.sample_rate as f64, freq_hz)
+        })
+    }
 
-                let pp_delay = (
-                    delay_sample.0 * (1.0 - width) + delay_sample.1 * width,
-                    delay_sample.1 * (1.0 - width) + delay_sample.0 * width,
-                );
+    // Rebuilds the filters in the feedback and dry paths from the settings, clearing their state.
+    fn rebuild_filters(&mut self, settings: &Settings) {
+        self.lowpass_filter =
+            Self::pass_filter(settings, Mode::LOWPASS, settings.lowpass_cutoffs());
+        self.lowpass_cutoff = settings.lowpass_cutoffs();
+        self.highpass_filter =
+            Self::pass_filter(settings, Mode::HIGHPASS, settings.highpass_cutoffs());
+        self.dry_filters = (self.lowpass_filter.clone(), self.highpass_filter.clone());
+        self.tilt_filter = Self::tilt_filter(settings);
+        self.peak_filter = Self::peak_filter(settings);
+        self.band_filter = Self::band_filter(settings);
+        self.allpass_filter = Self::allpass_filter(settings);
+    }
 
-                self.state.delay_buffer[self.state.delay_buffer_index] =
-                    (pp_input.0 + pp_delay.0, pp_input.1 + pp_delay.1);
-            } else {
-                self.state.delay_buffer[self.state.delay_buffer_index] = (
-                    input_sample.0 + delay_sample.0,
-                    input_sample.1 + delay_sample.1,
-                );
-            }
+    fn reset(&mut self, settings: &Settings) {
+        self.delay_buffer.fill(Frame::default());
+        self.delay_buffer_index = 0;
+        self.unfilled = 0;
+        self.delay_time
+            .reset(settings.swung_delay_times(settings.ping_pong));
+        self.reset_filters();
+        self.filter_env.reset();
+        self.lfo.reset();
+        self.wow_flutter.reset();
+        self.reverse_position = (0.0, 0.0);
+        self.feedback.reset(settings.effective_feedback());
+        self.dry_wet_mix.reset(settings.mix());
+        self.input_gain.reset(settings.input_gain);
+        self.output_level.reset(settings.output_level);
+        self.freeze.reset(switch_amount(settings.freeze));
+        self.reverse.reset(switch_amount(settings.reverse));
+        self.ping_pong.reset(switch_amount(settings.ping_pong));
+        self.bypass.reset(switch_amount(settings.bypass));
+        self.diffusion.reset(settings.diffusion.clamp(0.0, 1.0));
+        self.diffused.reset(switch_amount(settings.diffusion > 0.0));
+        self.since_input = 0;
+        self.bounce_gate.reset(1.0);
+        self.ducking.reset();
+        self.chorus.reset();
+        self.haas.reset();
+        self.granular.reset();
+        for filter in &mut self.tap_filters {
+            filter.reset();
+        }
+        self.dry_delay.fill(Frame::default());
+        self.dry_delay_index = 0;
+        self.meter.reset();
+    }
 
-            // Mix the dry and wet signals
-            let delay_sample = (
-                (1.0 - self.settings.dry_wet_mix) * input_sample.0
-                    + self.settings.dry_wet_mix * delay_sample.0,
-                (1.0 - self.settings.dry_wet_mix) * input_sample.1
-                    + self.settings.dry_wet_mix * delay_sample.1,
+    // Clears the state of every filter in the feedback path.
+    fn reset_filters(&mut self) {
+        self.lowpass_filter.reset();
+        self.highpass_filter.reset();
+        self.dry_filters.0.reset();
+        self.dry_filters.1.reset();
+        if let Some((low_shelf, high_shelf)) = &mut self.tilt_filter {
+            low_shelf.reset();
+            high_shelf.reset();
+        }
+        self.brighten_filter.reset();
+        if let Some(peak_filter) = &mut self.peak_filter {
+            peak_filter.reset();
+        }
+        if let Some(band_filter) = &mut self.band_filter {
+            band_filter.reset();
+        }
+        if let Some(allpass_filter) = &mut self.allpass_filter {
+            allpass_filter.reset();
+        }
+        self.diffuser.reset();
+        self.oversampler.reset();
+        self.decimator.reset();
+        self.dc_blocker.reset();
+    }
+
+    // Resizes the delay buffer to fit the settings, without shrinking it below
+    // the preallocated maximum delay, and makes a filter for every tap.
+    fn fit(&mut self, settings: &Settings) {
+        let len = buffer_len(settings, self.max_delay_ms);
+
+        // Keep the old delay times readable while crossfading or gliding away from them.
+        let len = match settings.time_change_mode {
+            TimeChangeMode::Hard => len,
+            _ => len.max(self.delay_buffer.len()),
+        };
+
+        self.resize(len);
+        self.tap_filters
+            .resize_with(settings.taps.len(), TapFilter::new);
+    }
+
+    // Resizes the delay buffer, keeping as much of the most recent audio as fits.
+    fn resize(&mut self, new_len: usize) {
+        let buffer = &mut self.delay_buffer;
+        let len = buffer.len();
+        if new_len == len {
+            return;
+        }
+
+        // Reorder the buffer so the oldest sample comes first.
+        buffer.rotate_left(self.delay_buffer_index);
+        self.delay_buffer_index = 0;
+
+        if new_len < len {
+            // Drop the oldest samples.
+            buffer.drain(..len - new_len);
+            self.unfilled = self.unfilled.saturating_sub(len - new_len);
+        } else if new_len > len {
+            // Pad with silence in front of the oldest sample.
+            buffer.resize(new_len, Frame::default());
+            buffer.rotate_right(new_len - len);
+            self.unfilled += new_len - len;
+        }
+    }
+
+    // Returns whether the delay times, in milliseconds, only reach audio that was
+    // written, rather than silence padded in by a resize.
+    fn can_read(&self, (left, right): (f32, f32), sample_rate: f32) -> bool {
+        let filled = self.delay_buffer.len() - self.unfilled;
+
+        delay_samples(left.max(right), sample_rate) < filled as f32
+    }
+
+    // Writes a sample at the write position, then advances it.
+    fn write(&mut self, sample: Frame<T>) {
+        self.delay_buffer[self.delay_buffer_index] = sample.map(flush_denormal);
+        self.delay_buffer_index = (self.delay_buffer_index + 1) % self.delay_buffer.len();
+        self.unfilled = self.unfilled.saturating_sub(1);
+    }
+
+    // Returns the sample that was written `delay` samples ago.
+    fn tap(&self, delay: usize) -> Frame<T> {
+        let len = self.delay_buffer.len();
+
+        self.delay_buffer[(self.delay_buffer_index + len - delay) % len]
+    }
+
+    // Reads the left and right channels at their own fractional delays, in samples.
+    fn read_stereo(&self, left: f32, right: f32, interpolation: Interpolation) -> Frame<T> {
+        Frame::new(
+            self.read(left, interpolation).l,
+            self.read(right, interpolation).r,
+        )
+    }
+
+    // Reads the delay buffer at a fractional delay, in samples.
+    fn read(&self, delay: f32, interpolation: Interpolation) -> Frame<T> {
+        let len = self.delay_buffer.len();
+
+        // The current sample hasn't been written yet, so the shortest possible
+        // delay is one sample, and the longest is the full buffer.
+        let delay = delay.clamp(1.0, len as f32);
+        let whole = delay as usize;
+        let a = self.tap(whole);
+
+        match interpolation {
+            Interpolation::None => a,
+            Interpolation::Linear => {
+                let b = self.tap((whole + 1).min(len));
+                let frac = T::from_f32(delay - whole as f32);
+
+                a + (b - a) * frac
+            }
+        }
+    }
+
+    // Runs one stereo frame through the delay line, feedback path and mix.
+    fn process_frame(
+        &mut self,
+        settings: &Settings,
+        input_sample: Frame<T>,
+        sidechain_sample: Frame<T>,
+        ping_pong: bool,
+    ) -> Frame<T> {
+        let (dry_sample, wet_sample) =
+            self.process_split(settings, input_sample, sidechain_sample, ping_pong);
+
+        self.mix(settings, input_sample, dry_sample, wet_sample)
+    }
+
+    // Mixes the dry and wet signals of one frame into the output.
+    fn mix(
+        &mut self,
+        settings: &Settings,
+        input_sample: Frame<T>,
+        dry_sample: Frame<T>,
+        wet_sample: Frame<T>,
+    ) -> Frame<T> {
+        let sample_rate = settings.sample_rate;
+        let smoothing_time_ms = settings.smoothing_time_ms;
+        self.dry_wet_mix.set_time(smoothing_time_ms, sample_rate);
+        self.output_level.set_time(smoothing_time_ms, sample_rate);
+        self.bypass.set_time(smoothing_time_ms, sample_rate);
+
+        let mix = self.dry_wet_mix.next(settings.mix());
+        let mix = match self.control {
+            Some((ModTarget::Mix, value)) => (mix + value).clamp(0.0, 1.0),
+            _ => mix,
+        };
+        let (dry_gain, wet_gain) = settings.mix_law.gains(mix);
+        let output_level = T::from_f32(self.output_level.next(settings.output_level));
+        let bypass = T::from_f32(self.bypass.next(switch_amount(settings.bypass)));
+
+        // Mix the dry and wet signals
+        let delay_sample = dry_sample * T::from_f32(dry_gain) + wet_sample * T::from_f32(wet_gain);
+
+        // Apply output level by scaling the delayed sample by the current output level.
+        let delay_sample = delay_sample * output_level;
+
+        // Pan the output, keeping the total power constant.
+        let delay_sample = match settings.balance {
+            0.0 => delay_sample,
+            balance => {
+                let angle = (balance.clamp(-1.0, 1.0) + 1.0) * core::f32::consts::FRAC_PI_4;
+                let (left, right) = (cosf(angle), sinf(angle));
+
+                Frame::new(
+                    delay_sample.l * T::from_f32(left * core::f32::consts::SQRT_2),
+                    delay_sample.r * T::from_f32(right * core::f32::consts::SQRT_2),
+                )
+            }
+        };
+
+        // Crossfade to the untouched input while bypassed.
+        delay_sample * (T::ONE - bypass) + input_sample * bypass
+    }
+
+    // Runs one stereo frame through the delay line and feedback path, returning
+    // the dry and wet signals before they're mixed.
+    fn process_split(
+        &mut self,
+        settings: &Settings,
+        input_sample: Frame<T>,
+        sidechain_sample: Frame<T>,
+        ping_pong: bool,
+    ) -> (Frame<T>, Frame<T>) {
+        let (dry_sample, wet_sample) =
+            self.process_unfiltered(settings, input_sample, sidechain_sample, ping_pong);
+
+        let dry_sample = match settings.filter_dry {
+            true => {
+                let (lowpass, highpass) = &mut self.dry_filters;
+                Self::filter(settings, (lowpass, highpass), dry_sample)
+            }
+            false => dry_sample,
+        };
+        let dry_sample = self.delay_dry(dry_sample, settings.wet_latency());
+
+        (dry_sample, wet_sample)
+    }
+
+    // Runs a block of stereo frames, held in separate left and right buffers of
+    // up to `DRY_BLOCK` frames, through the whole delay, overwriting them with
+    // the output.
+    //
+    // The dry filters run over the whole block at once, so this is only the same
+    // as `process_frame` while their cutoffs hold still, without the filter envelope.
+    fn process_block_filtered(&mut self, settings: &Settings, left: &mut [T], right: &mut [T]) {
+        let mut inputs = [Frame::default(); DRY_BLOCK];
+        let mut wets = [Frame::default(); DRY_BLOCK];
+
+        for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            let input_sample = Frame::new(*l, *r);
+            let (dry_sample, wet_sample) =
+                self.process_unfiltered(settings, input_sample, input_sample, settings.ping_pong);
+
+            inputs[i] = input_sample;
+            wets[i] = wet_sample;
+            (*l, *r) = dry_sample.into();
+        }
+
+        let (lowpass, highpass) = &mut self.dry_filters;
+        Self::filter_block(settings, (lowpass, highpass), left, right);
+
+        let latency = settings.wet_latency();
+        for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            let dry_sample = self.delay_dry(Frame::new(*l, *r), latency);
+            (*l, *r) = self.mix(settings, inputs[i], dry_sample, wets[i]).into();
+        }
+    }
+
+    // Runs one stereo frame through the delay line and feedback path, returning
+    // the wet signal, and the dry signal before it's filtered and lined up with
+    // the wet one.
+    fn process_unfiltered(
+        &mut self,
+        settings: &Settings,
+        input_sample: Frame<T>,
+        sidechain_sample: Frame<T>,
+        ping_pong: bool,
+    ) -> (Frame<T>, Frame<T>) {
+        let sample_rate = settings.sample_rate;
+        // Growing the buffer pads silence behind the oldest audio, so hold a
+        // crossfade back until the new delay times reach audio that was written.
+        let target = settings.swung_delay_times(ping_pong);
+        let target = match settings.time_change_mode {
+            TimeChangeMode::Crossfade if !self.can_read(target, sample_rate) => {
+                self.delay_time.current()
+            }
+            _ => target,
+        };
+        let delay_times = self
+            .delay_time
+            .next(settings.time_change_mode, target, sample_rate);
+        let (delay_left, delay_right) = delay_times.current;
+
+        // Sweep both delay times with the LFO.
+        let modulation = settings.mod_depth_ms
+            * self
+                .lfo
+                .next(settings.mod_rate_hz, sample_rate, settings.mod_waveform);
+
+        // Wobble both delay times like a worn tape transport.
+        let modulation = modulation
+            + self.wow_flutter.next(
+                settings.wow_depth_ms,
+                settings.wow_rate_hz,
+                settings.flutter_depth_ms,
+                settings.flutter_rate_hz,
+                sample_rate,
+            );
+        let modulation = match self.control {
+            Some((ModTarget::DelayTime, value)) => modulation + value,
+            _ => modulation,
+        };
+        let (delay_left, delay_right) = (delay_left + modulation, delay_right + modulation);
+
+        // Ramp the gain parameters towards their current settings.
+        let smoothing_time_ms = settings.smoothing_time_ms;
+        self.feedback.set_time(smoothing_time_ms, sample_rate);
+        self.input_gain.set_time(smoothing_time_ms, sample_rate);
+        self.freeze.set_time(smoothing_time_ms, sample_rate);
+        self.reverse.set_time(smoothing_time_ms, sample_rate);
+        self.ping_pong.set_time(smoothing_time_ms, sample_rate);
+        self.bounce_gate.set_time(smoothing_time_ms, sample_rate);
+        self.diffusion.set_time(smoothing_time_ms, sample_rate);
+        self.diffused.set_time(smoothing_time_ms, sample_rate);
+
+        // The settings are public, so guard against feedback that was set directly.
+        let feedback = self.feedback.next(settings.effective_feedback());
+        let feedback = match self.control {
+            Some((ModTarget::Feedback, value)) => clamp_feedback(feedback + value),
+            _ => feedback,
+        };
+        let input_gain = T::from_f32(self.input_gain.next(settings.input_gain));
+        let freeze = self.freeze.next(switch_amount(settings.freeze));
+        let reverse = self.reverse.next(switch_amount(settings.reverse));
+        let diffusion = self.diffusion.next(settings.diffusion.clamp(0.0, 1.0));
+        let diffused = self.diffused.next(switch_amount(settings.diffusion > 0.0));
+
+        // Attenuate the wet signal by the steady-state gain of the feedback loop.
+        let auto_gain = match settings.auto_gain && feedback > 0.0 {
+            true => T::from_f32(((1.0 - feedback) / feedback).min(1.0)),
+            false => T::ONE,
+        };
+
+        let brighten = brighten_gain(settings.repeat_brighten_db, feedback);
+
+        // Cut the feedback once the repeats have bounced enough times since the input went quiet.
+        let input_level = input_sample.to_f32();
+        self.since_input = match input_level.l.abs().max(input_level.r.abs()) > BOUNCE_SILENCE {
+            true => 0,
+            false => self.since_input.saturating_add(1),
+        };
+        let bouncing = match settings.ping_pong_bounces {
+            Some(bounces) => {
+                let since_input_ms = self.since_input as f32 / sample_rate * 1000.0;
+                // The feedback also sets the level of what's heard, so cut it
+                // halfway between the last repeat and the one after it.
+                since_input_ms < (bounces as f32 + 0.5) * delay_left.max(delay_right)
+            }
+            None => true,
+        };
+        let feedback = feedback * self.bounce_gate.next(switch_amount(bouncing));
+
+        // Freezing holds the feedback at unity, so the repeats don't decay.
+        let feedback = T::from_f32(feedback + (1.0 - feedback) * freeze);
+        let freeze = T::from_f32(freeze);
+
+        // Read the repeats early by the oversampling and diffuser latency, so they
+        // stay in time. The diffuser's moves along with its crossfade.
+        self.oversampler.set_factor(settings.oversample);
+        let oversampling = self.oversampler.is_enabled() && settings.saturation != Saturation::None;
+        let (diffuser_left, diffuser_right) = diffuser::latency(sample_rate);
+        let latency = settings.oversampling_latency();
+        let latency_left = latency + diffuser_left * diffused;
+        let latency_right = latency + diffuser_right * diffused;
+        let delay_left = delay_samples(delay_left, sample_rate) - latency_left;
+        let delay_right = delay_samples(delay_right, sample_rate) - latency_right;
+
+        // Read each channel at its own delay time.
+        let interpolation = match settings.resonator {
+            true => Interpolation::Linear,
+            false => settings.interpolation,
+        };
+        let reversed = match reverse > 0.0 {
+            true => {
+                let fade = REVERSE_FADE_MS / 1000.0 * sample_rate;
+                let (position_left, position_right) = &mut self.reverse_position;
+                let (delay_left, gain_left) = reverse_window(position_left, delay_left, fade);
+                let (delay_right, gain_right) = reverse_window(position_right, delay_right, fade);
+
+                Frame::new(
+                    self.read(delay_left, interpolation).l * T::from_f32(gain_left),
+                    self.read(delay_right, interpolation).r * T::from_f32(gain_right),
+                )
+            }
+            false => Frame::default(),
+        };
+        let forward = if reverse >= 1.0 {
+            Frame::default()
+        } else if let Some(params) = settings.granular {
+            self.granular.process(
+                &self.delay_buffer,
+                self.delay_buffer_index,
+                params,
+                (delay_left, delay_right),
+                sample_rate,
+            )
+        } else if delay_times.fade < 1.0 {
+            // Crossfade from the old delay times to the new ones.
+            let (previous_left, previous_right) = delay_times.previous;
+            let previous = self.read_stereo(
+                delay_samples(previous_left + modulation, sample_rate) - latency_left,
+                delay_samples(previous_right + modulation, sample_rate) - latency_right,
+                interpolation,
+            );
+            let current = self.read_stereo(delay_left, delay_right, interpolation);
+            let fade = T::from_f32(delay_times.fade);
+
+            previous * (T::ONE - fade) + current * fade
+        } else {
+            self.read_stereo(delay_left, delay_right, interpolation)
+        };
+
+        // Crossfade between reading forwards and backwards, so switching doesn't click.
+        let delay_sample = reversed * T::from_f32(reverse) + forward * T::from_f32(1.0 - reverse);
+
+        // Keep the untouched delay sample, which is looped back while frozen.
+        let frozen_sample = delay_sample;
+
+        // Apply feedback by scaling the delay sample by the current feedback level.
+        let delay_sample = delay_sample * feedback;
+
+        // Apply phase reverse by inverting the phase of the delay sample. The
+        // resonator skips it, as inverting every pass would halve its pitch.
+        let delay_sample = match settings.resonator {
+            true => delay_sample,
+            false => settings.phase_reverse.apply(delay_sample),
+        };
+
+        // Open the lowpass filter while the input is loud.
+        self.filter_env
+            .set_times(FILTER_ENV_ATTACK_MS, settings.filter_env_rate, sample_rate);
+        let input_level = input_sample.to_f32();
+        let envelope = self
+            .filter_env
+            .process(input_level.l.abs().max(input_level.r.abs()));
+        let (left, right) = settings.lowpass_cutoffs();
+        let octaves = settings.filter_env_amount * envelope.min(1.0);
+        let octaves = match self.control {
+            Some((ModTarget::Cutoff, value)) => octaves + value,
+            _ => octaves,
+        };
+        let lowpass_cutoff = match octaves {
+            0.0 => (left, right),
+            octaves => {
+                let opening = pow(2.0, octaves as f64);
+                (left * opening, right * opening)
+            }
+        };
+        if lowpass_cutoff != self.lowpass_cutoff {
+            self.set_lowpass_cutoffs(sample_rate as f64, lowpass_cutoff);
+        }
+
+        let delay_sample = match settings.filter_in_feedback {
+            true => Self::filter(
+                settings,
+                (&mut self.lowpass_filter, &mut self.highpass_filter),
+                delay_sample,
+            ),
+            false => delay_sample,
+        };
+        let delay_sample = match &mut self.tilt_filter {
+            Some((low_shelf, high_shelf)) => high_shelf.process(low_shelf.process(delay_sample)),
+            None => delay_sample,
+        };
+        let delay_sample = match brighten {
+            Some(gain) => {
+                self.brighten_filter.set_shelf_gain(gain as f64);
+                self.brighten_filter.process(delay_sample)
+            }
+            None => delay_sample,
+        };
+        let delay_sample = match &mut self.peak_filter {
+            Some(peak_filter) => peak_filter.process(delay_sample),
+            None => delay_sample,
+        };
+        let delay_sample = match &mut self.band_filter {
+            Some(band_filter) => band_filter.process(delay_sample),
+            None => delay_sample,
+        };
+        let delay_sample = match &mut self.allpass_filter {
+            Some(allpass_filter) => allpass_filter.process(delay_sample),
+            None => delay_sample,
+        };
+
+        // Crossfade the diffuser in and out, so switching it doesn't click. It's
+        // always run, so it's ready to be crossfaded to.
+        let diffused_sample = self.diffuser.process(delay_sample, diffusion);
+        let delay_sample = match diffused {
+            0.0 => delay_sample,
+            1.0 => diffused_sample,
+            diffused => {
+                let diffused = T::from_f32(diffused);
+                delay_sample * (T::ONE - diffused) + diffused_sample * diffused
+            }
+        };
+
+        // Apply saturation, so the repeats are progressively coloured as they feed back.
+        let saturation = settings.saturation;
+        let drive = settings.drive;
+        let saturate = |frame: Frame<T>| frame.map(|sample| saturation.process(sample, drive));
+        let delay_sample = match oversampling {
+            true => self.oversampler.process(delay_sample, saturate),
+            false => saturate(delay_sample),
+        };
+
+        // Degrade the repeats, so the degradation compounds on every pass.
+        let delay_sample = match settings.bit_depth {
+            Some(bits) => delay_sample.map(|sample| quantize(sample, bits)),
+            None => delay_sample,
+        };
+        let delay_sample = match settings.downsample_factor {
+            Some(factor) => self.decimator.process(delay_sample, factor),
+            None => delay_sample,
+        };
+
+        // Block any DC offset before it can accumulate over the repeats.
+        let delay_sample = match settings.dc_block {
+            true => self.dc_blocker.process(delay_sample),
+            false => delay_sample,
+        };
+
+        // Bleed some of each channel's repeats into the other channel's feedback.
+        let cross_feedback = settings.cross_feedback.clamp(0.0, 1.0);
+        let feedback_delay = delay_sample * T::from_f32(1.0 - cross_feedback)
+            + delay_sample.swap() * T::from_f32(cross_feedback);
+
+        // Only the input into the delay line is trimmed, not the dry signal.
+        let delay_input = input_sample * input_gain;
+
+        // Apply ping-pong by mixing the left and right channels of the delay sample.
+        // Switching crossfades between the two routings, so it doesn't click.
+        let ping_pong = self.ping_pong.next(switch_amount(ping_pong));
+        let bouncing = match ping_pong > 0.0 {
+            true => {
+                let width = match settings.stereo_mode {
+                    StereoMode::PingPong => T::from_f32(settings.width.clamp(0.0, 1.0)),
+                    StereoMode::MidSide => T::ONE,
+                };
+                let half = T::from_f32(0.5);
+
+                // At full width, the input enters on the right and the repeats swap sides.
+                let stereo = Frame::new(T::ZERO, delay_input.r) + feedback_delay.swap();
+
+                // At zero width, both channels carry the same mono sum.
+                let mono = Frame::mono((delay_input.l + delay_input.r) * half)
+                    + Frame::mono((feedback_delay.l + feedback_delay.r) * half);
+
+                stereo * width + mono * (T::ONE - width)
+            }
+            false => Frame::default(),
+        };
+        let straight = match ping_pong < 1.0 {
+            true => delay_input + feedback_delay,
+            false => Frame::default(),
+        };
+        let feedback_sample =
+            bouncing * T::from_f32(ping_pong) + straight * T::from_f32(1.0 - ping_pong);
+
+        // Crossfade towards looping the buffer contents unchanged while frozen.
+        let feedback_sample = feedback_sample * (T::ONE - freeze) + frozen_sample * freeze;
+
+        // Sum the multi-tap reads, if there are any, before the new sample is written.
+        let wet_sample = match settings.taps.is_empty() {
+            true => delay_sample,
+            false => {
+                let mut sum = Frame::default();
+                for (i, tap) in settings.taps.iter().enumerate() {
+                    let tap_sample =
+                        self.read(delay_samples(tap.time_ms, sample_rate), interpolation);
+
+                    // Taps assigned without `set_taps` may not have a filter yet.
+                    let tap_sample = match self.tap_filters.get_mut(i) {
+                        Some(filter) => filter.process(tap_sample, tap.lowpass, sample_rate as f64),
+                        None => tap_sample,
+                    };
+
+                    sum = sum + tap.apply(tap_sample);
+                }
+
+                sum
+            }
+        };
+
+        // Filter the wet signal on its way out, rather than on every repeat.
+        let wet_sample = match settings.filter_in_feedback {
+            true => wet_sample,
+            false => Self::filter(
+                settings,
+                (&mut self.lowpass_filter, &mut self.highpass_filter),
+                wet_sample,
+            ),
+        };
+
+        // A NaN or infinity would loop around the feedback path forever, so drop it,
+        // along with any filter state it's already reached.
+        let finite = |frame: Frame<T>| frame.l.is_finite() && frame.r.is_finite();
+        let (feedback_sample, wet_sample) = match finite(feedback_sample) && finite(wet_sample) {
+            true => (feedback_sample, wet_sample),
+            false => {
+                self.nan_count = self.nan_count.saturating_add(1);
+                self.reset_filters();
+
+                (Frame::default(), Frame::default())
+            }
+        };
+
+        self.write(feedback_sample);
+
+        let wet_sample = wet_sample * auto_gain;
+
+        // Widen or narrow the wet signal by scaling its side component.
+        let wet_sample = match settings.stereo_mode {
+            StereoMode::PingPong => wet_sample,
+            StereoMode::MidSide => {
+                mid_side_width(wet_sample, T::from_f32(settings.ms_width.max(0.0)))
+            }
+        };
+
+        // Duck the wet signal while the sidechain, usually the input, is loud.
+        self.ducking.set_times(
+            settings.duck_attack_ms,
+            settings.duck_release_ms,
+            sample_rate,
+        );
+        let sidechain_level = sidechain_sample.to_f32();
+        let envelope = self
+            .ducking
+            .process(sidechain_level.l.abs().max(sidechain_level.r.abs()));
+        let wet_sample = wet_sample
+            * T::from_f32(1.0 - settings.duck_amount.clamp(0.0, 1.0) * envelope.min(1.0));
+
+        // Meter the wet signal, as it's heard.
+        self.meter.set_times(
+            settings.meter_rms_window_ms,
+            settings.meter_release_ms,
+            sample_rate,
+        );
+        self.meter.process(wet_sample.to_f32());
+
+        // Thicken the dry signal with the chorus.
+        let dry_sample = match settings.chorus {
+            Some(params) => self.chorus.process(input_sample, params, sample_rate),
+            None => input_sample,
+        };
+        let dry_sample = match settings.dry_haas_ms {
+            0.0 => dry_sample,
+            delay_ms => self.haas.process(dry_sample, delay_ms, sample_rate),
+        };
+
+        (dry_sample, wet_sample)
+    }
+
+    // Runs a frame through a pair of lowpass and highpass filters.
+    fn filter(
+        settings: &Settings,
+        (lowpass, highpass): (&mut StereoFilter<T>, &mut StereoFilter<T>),
+        sample: Frame<T>,
+    ) -> Frame<T> {
+        // Apply filtering by convolving the sample with the filter coefficients.
+        match (settings.lowpass_enabled, settings.highpass_enabled) {
+            (true, true) => match settings.filter_routing {
+                FilterRouting::LpThenHp => highpass.process(lowpass.process(sample)),
+                FilterRouting::HpThenLp => lowpass.process(highpass.process(sample)),
+                FilterRouting::Parallel => {
+                    let lowpassed = lowpass.process(sample);
+                    let highpassed = highpass.process(sample);
+
+                    (lowpassed + highpassed) * T::from_f32(0.5)
+                }
+            },
+            (true, false) => lowpass.process(sample),
+            (false, true) => highpass.process(sample),
+            (false, false) => sample,
+        }
+    }
+
+    // Filters separate left and right buffers in place, like `filter` does one
+    // frame. They hold at most `DRY_BLOCK` frames.
+    fn filter_block(
+        settings: &Settings,
+        (lowpass, highpass): (&mut StereoFilter<T>, &mut StereoFilter<T>),
+        left: &mut [T],
+        right: &mut [T],
+    ) {
+        match (settings.lowpass_enabled, settings.highpass_enabled) {
+            (true, true) => match settings.filter_routing {
+                FilterRouting::LpThenHp => {
+                    lowpass.process_block(left, right);
+                    highpass.process_block(left, right);
+                }
+                FilterRouting::HpThenLp => {
+                    highpass.process_block(left, right);
+                    lowpass.process_block(left, right);
+                }
+                FilterRouting::Parallel => {
+                    let len = left.len();
+                    let mut highpassed = ([T::ZERO; DRY_BLOCK], [T::ZERO; DRY_BLOCK]);
+                    highpassed.0[..len].copy_from_slice(left);
+                    highpassed.1[..len].copy_from_slice(right);
+
+                    lowpass.process_block(left, right);
+                    highpass.process_block(&mut highpassed.0[..len], &mut highpassed.1[..len]);
+
+                    let half = T::from_f32(0.5);
+                    for (sample, highpassed) in left.iter_mut().zip(&highpassed.0[..len]) {
+                        *sample = (*sample + *highpassed) * half;
+                    }
+                    for (sample, highpassed) in right.iter_mut().zip(&highpassed.1[..len]) {
+                        *sample = (*sample + *highpassed) * half;
+                    }
+                }
+            },
+            (true, false) => lowpass.process_block(left, right),
+            (false, true) => highpass.process_block(left, right),
+            (false, false) => {}
+        }
+    }
+
+    // Moves the cutoffs of both lowpass filters, in the feedback and dry paths.
+    fn set_lowpass_cutoffs(&mut self, sample_rate: f64, cutoffs: (f64, f64)) {
+        self.lowpass_filter.set_cutoffs(sample_rate, cutoffs);
+        self.dry_filters.0.set_cutoffs(sample_rate, cutoffs);
+        self.lowpass_cutoff = cutoffs;
+    }
+
+    // Delays the dry signal by `latency` frames, up to the length of the line.
+    fn delay_dry(&mut self, dry_sample: Frame<T>, latency: usize) -> Frame<T> {
+        let len = self.dry_delay.len();
+        self.dry_delay[self.dry_delay_index] = dry_sample;
+        let delayed = self.dry_delay[(self.dry_delay_index + len - latency.min(len - 1)) % len];
+        self.dry_delay_index = (self.dry_delay_index + 1) % len;
+
+        delayed
+    }
+
+    // Moves the cutoffs of both highpass filters, in the feedback and dry paths.
+    fn set_highpass_cutoffs(&mut self, sample_rate: f64, cutoffs: (f64, f64)) {
+        self.highpass_filter.set_cutoffs(sample_rate, cutoffs);
+        self.dry_filters.1.set_cutoffs(sample_rate, cutoffs);
+    }
+}
+
+impl Delay {
+    /// Creates a new `Delay` instance with the specified settings, running at
+    /// their `sample_rate`. See [`Delay::with_sample_rate`] to pass it separately.
+    ///
+    /// It processes `f32` audio. [`Delay::with_settings`] creates one for any
+    /// other [`Sample`] type.
+    pub fn new(settings: Settings) -> Self {
+        Self::with_settings(settings)
+    }
+
+    /// Creates a new `Delay` running at `sample_rate`, overriding the settings'
+    /// `sample_rate`.
+    ///
+    /// [`Delay::new`] takes the sample rate from the settings, which defaults to
+    /// 44.1kHz. This makes the rate explicit, so the delay buffer and filters are
+    /// always sized for the host's rate.
+    pub fn with_sample_rate(settings: Settings, sample_rate: f32) -> Self {
+        Self::new(Settings {
+            sample_rate,
+            ..settings
+        })
+    }
+}
+
+impl<T: Sample> Delay<T> {
+    /// Creates a new `Delay` processing any [`Sample`] type, like
+    /// `Delay::<f64>::with_settings(settings)`. It's otherwise the same as [`Delay::new`].
+    ///
+    /// The settings aren't validated, beyond clamping the feedback and running at
+    /// the default 44.1kHz in place of a sample rate that isn't positive and finite.
+    /// Use [`Settings::validate`] or [`Settings::builder`] to catch configuration mistakes.
+    pub fn with_settings(mut settings: Settings) -> Self {
+        settings.feedback = clamp_feedback(settings.feedback);
+        if !valid_sample_rate(settings.sample_rate) {
+            settings.sample_rate = DEFAULT_SAMPLE_RATE;
+        }
+
+        let state = State::new(&settings);
+
+        Self {
+            settings,
+            state,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Creates a new `Delay` with its delay buffer preallocated for delay times
+    /// of up to `max_ms` milliseconds.
+    ///
+    /// Changing the delay time within that range only moves the read position,
+    /// so [`Delay::set_delay_time`] never allocates, which makes it safe to call
+    /// on the audio thread. Longer delay times still grow the buffer to fit.
+    pub fn with_max_delay(mut settings: Settings, max_ms: f32) -> Self {
+        settings.feedback = clamp_feedback(settings.feedback);
+        if !valid_sample_rate(settings.sample_rate) {
+            settings.sample_rate = DEFAULT_SAMPLE_RATE;
+        }
+
+        let state = State::with_max_delay(&settings, max_ms);
+
+        Self {
+            settings,
+            state,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Changes the sample rate of the delay effect.
+    ///
+    /// This reallocates the delay buffer and rebuilds the filter coefficients,
+    /// so any audio currently in the delay line is discarded. A sample rate that
+    /// isn't positive and finite is ignored, keeping the current one.
+    pub fn set_sample_rate(&mut self, sr: f32) {
+        if !valid_sample_rate(sr) {
+            return;
+        }
+
+        self.settings.sample_rate = sr;
+        self.state = State::with_max_delay(&self.settings, self.state.max_delay_ms);
+        for channel in &mut self.channels {
+            *channel = self.state.clone();
+        }
+    }
+
+    /// Replaces all of the settings, updating the internal state to match.
+    ///
+    /// Only what changed is updated. The delay buffer is resized to fit the new
+    /// delay times, and the filter cutoffs move without clearing the filters, like
+    /// [`Delay::set_lowpass`]. Changing the filter type, slope or resonance, or the
+    /// shelving, peaking or allpass filters, rebuilds the filters, and changing the
+    /// sample rate rebuilds everything, like [`Delay::set_sample_rate`], which also
+    /// ignores a sample rate that isn't positive and finite. Every other setting is
+    /// read as the audio is processed.
+    ///
+    /// Assigning to [`Delay::settings`] directly skips these updates, so prefer this.
+    /// It returns straight away when nothing changed, so it's cheap to call with
+    /// the host's parameters on every block.
+    pub fn set_settings(&mut self, mut settings: Settings) {
+        if !valid_sample_rate(settings.sample_rate) {
+            settings.sample_rate = self.settings.sample_rate;
+        }
+        if settings == self.settings {
+            return;
+        }
+
+        let previous = core::mem::replace(&mut self.settings, settings);
+        let settings = &self.settings;
+
+        if settings.sample_rate != previous.sample_rate {
+            self.set_sample_rate(settings.sample_rate);
+            return;
+        }
+
+        let rebuild = settings.filter_type != previous.filter_type
+            || settings.filter_slope != previous.filter_slope
+            || settings.resonance != previous.resonance
+            || settings.shelf_gain_db != previous.shelf_gain_db
+            || settings.shelf_freq != previous.shelf_freq
+            || settings.peak_filter != previous.peak_filter
+            || settings.band_filter != previous.band_filter
+            || settings.allpass_filter != previous.allpass_filter;
+
+        let sample_rate = settings.sample_rate as f64;
+        for state in core::iter::once(&mut self.state).chain(&mut self.channels) {
+            if rebuild {
+                state.rebuild_filters(settings);
+                continue;
+            }
+            if settings.lowpass_cutoffs() != previous.lowpass_cutoffs() {
+                state.set_lowpass_cutoffs(sample_rate, settings.lowpass_cutoffs());
+            }
+            if settings.highpass_cutoffs() != previous.highpass_cutoffs() {
+                state.set_highpass_cutoffs(sample_rate, settings.highpass_cutoffs());
+            }
+        }
+
+        self.fit();
+    }
+
+    /// Changes the feedback level, clamped to `[0.0, MAX_FEEDBACK]`.
+    ///
+    /// The change is smoothed over `smoothing_time_ms`.
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.settings.feedback = clamp_feedback(feedback);
+        self.settings.decay_time = None;
+    }
+
+    /// Changes the feedback level, in decibels per repeat, like [`Delay::set_feedback`].
+    pub fn set_feedback_db(&mut self, db: f32) {
+        self.set_feedback(Settings::feedback_from_db(db));
+    }
+
+    /// Changes how long the repeats take to fade by 60 dB, in seconds. The
+    /// feedback then follows the delay time, so the decay stays the same length
+    /// as the delay time changes, until [`Delay::set_feedback`] is called.
+    pub fn set_decay_time(&mut self, seconds: f32) {
+        self.settings.decay_time = Some(seconds);
+    }
+
+    /// Changes the dry/wet mix. The change is smoothed over `smoothing_time_ms`.
+    pub fn set_dry_wet_mix(&mut self, dry_wet_mix: f32) {
+        self.settings.dry_wet_mix = dry_wet_mix;
+    }
+
+    /// Solos the wet signal, leaving `dry_wet_mix` untouched for when it's turned off.
+    pub fn solo_wet(&mut self, wet_only: bool) {
+        self.settings.wet_only = wet_only;
+    }
+
+    /// Changes the ping-pong width, clamped to `[0.0, 1.0]`. See [`Settings::width`]
+    /// for how it's applied.
+    pub fn set_width(&mut self, width: f32) {
+        self.settings.width = width.clamp(0.0, 1.0);
+    }
+
+    /// Changes the gain of the input into the delay line. The dry signal is
+    /// unaffected. The change is smoothed over `smoothing_time_ms`.
+    pub fn set_input_gain(&mut self, input_gain: f32) {
+        self.settings.input_gain = input_gain;
+    }
+
+    /// Changes the output level. The change is smoothed over `smoothing_time_ms`.
+    pub fn set_output_level(&mut self, output_level: f32) {
+        self.settings.output_level = output_level;
+    }
+
+    /// Changes the output level, in decibels. The change is smoothed over `smoothing_time_ms`.
+    pub fn set_output_level_db(&mut self, db: f32) {
+        self.set_output_level(Settings::output_level_from_db(db));
+    }
+
+    /// Switches ping-pong on or off. The switch crossfades over `smoothing_time_ms`.
+    pub fn set_ping_pong(&mut self, ping_pong: bool) {
+        self.settings.ping_pong = ping_pong;
+    }
+
+    /// Switches reverse playback on or off, resizing the delay buffer to fit.
+    ///
+    /// The switch crossfades over `smoothing_time_ms`.
+    pub fn set_reverse(&mut self, reverse: bool) {
+        self.settings.reverse = reverse;
+        self.fit();
+    }
+
+    /// Replaces the multi-tap taps, resizing the delay buffer to fit the longest one.
+    pub fn set_taps(&mut self, taps: Vec<Tap>) {
+        self.settings.taps = taps;
+        self.fit();
+    }
+
+    /// Syncs the delay time to a note division at the given tempo.
+    ///
+    /// This resizes the delay buffer in the same way as [`Delay::set_delay_time`].
+    pub fn set_tempo_sync(&mut self, bpm: f32, div: NoteDivision) {
+        self.set_delay_time(Settings::delay_time_from_tempo(bpm, div));
+    }
+
+    /// Freezes or unfreezes the delay.
+    ///
+    /// While frozen, the buffer contents loop indefinitely and new input is not
+    /// written to the delay line, although the dry signal still passes through.
+    /// Freezing and unfreezing crossfade over `smoothing_time_ms`, so neither clicks.
+    pub fn set_freeze(&mut self, freeze: bool) {
+        self.settings.freeze = freeze;
+    }
+
+    /// Bypasses the effect, or brings it back in.
+    ///
+    /// The delay keeps running while bypassed, and the switch crossfades over
+    /// `smoothing_time_ms`, so it doesn't click or cut the tail.
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.settings.bypass = bypass;
+    }
+
+    /// Clears the delay buffer and the filter state, silencing any tail.
+    ///
+    /// The settings are left untouched.
+    pub fn reset(&mut self) {
+        self.state.reset(&self.settings);
+        for channel in &mut self.channels {
+            channel.reset(&self.settings);
+        }
+    }
+
+    /// Takes a snapshot of the delay's settings and internal state.
+    ///
+    /// This copies the whole delay buffer, so it allocates and shouldn't be
+    /// called on the audio thread.
+    pub fn save_state(&self) -> DelayState<T> {
+        DelayState {
+            settings: self.settings.clone(),
+            state: self.state.clone(),
+            channels: self.channels.clone(),
+        }
+    }
+
+    /// Restores a snapshot taken with [`Delay::save_state`], replacing the
+    /// current settings and internal state.
+    ///
+    /// Processing then carries on exactly as it did after the snapshot was taken,
+    /// which makes renders reproducible after seeking.
+    pub fn load_state(&mut self, state: DelayState<T>) {
+        self.settings = state.settings;
+        self.state = state.state;
+        self.channels = state.channels;
+    }
+
+    /// Changes the delay time, in milliseconds.
+    ///
+    /// The delay buffer is resized to match the new delay time. The most recent
+    /// audio in the buffer is kept, so the existing tail carries on ringing out
+    /// at the new delay time. When shrinking, the oldest audio is discarded.
+    ///
+    /// A `Delay` created with [`Delay::with_max_delay`] keeps its buffer, and
+    /// doesn't allocate, as long as the delay time stays within the maximum.
+    ///
+    /// With [`TimeChangeMode::Hard`], this change is not click-free: the read
+    /// position jumps to a different point in the tail, which can produce a
+    /// discontinuity in the wet signal. Set `time_change_mode` to crossfade or
+    /// glide to the new delay time instead.
+    pub fn set_delay_time(&mut self, ms: f32) {
+        self.settings.delay_time = ms;
+        self.fit();
+    }
+
+    /// Changes the right channel's delay time, in milliseconds.
+    ///
+    /// `None` makes the right channel follow `delay_time` again. The delay buffer
+    /// is resized in the same way as [`Delay::set_delay_time`].
+    pub fn set_delay_time_right(&mut self, ms: Option<f32>) {
+        self.settings.delay_time_right = ms;
+        self.fit();
+    }
+
+    /// Switches the resonator on or off, resizing the delay buffer to fit.
+    pub fn set_resonator(&mut self, resonator: bool) {
+        self.settings.resonator = resonator;
+        self.fit();
+    }
+
+    /// Changes the pitch of the resonator, in Hz, resizing the delay buffer to fit.
+    /// Pitches below 1 Hz are raised to 1 Hz.
+    ///
+    /// ```rust
+    /// use aych_delay::{Delay, Settings};
+    ///
+    /// let mut delay: Delay = Delay::new(Settings {
+    ///     sample_rate: 44100.0,
+    ///     resonator: true,
+    ///     ..Settings::default()
+    /// });
+    /// delay.set_tune_hz(220.0);
+    ///
+    /// // The response repeats once per period of the pitch, which is about 200 samples.
+    /// let response: Vec<f32> = delay
+    ///     .impulse_response(8820)
+    ///     .iter()
+    ///     .map(|(left, right)| left + right)
+    ///     .collect();
+    /// let correlation = |lag: usize| -> f32 {
+    ///     response.iter().zip(&response[lag..]).map(|(a, b)| a * b).sum()
+    /// };
+    /// let period = (100..600)
+    ///     .max_by(|&a, &b| correlation(a).total_cmp(&correlation(b)))
+    ///     .unwrap();
+    ///
+    /// assert!((199..=202).contains(&period));
+    /// ```
+    pub fn set_tune_hz(&mut self, tune_hz: f32) {
+        self.settings.tune_hz = tune_hz.max(MIN_TUNE_HZ);
+        self.fit();
+    }
+
+    /// Changes the offset between the left and right delay times, in milliseconds.
+    ///
+    /// The delay buffer is resized in the same way as [`Delay::set_delay_time`].
+    pub fn set_stereo_offset(&mut self, ms: f32) {
+        self.settings.stereo_offset_ms = ms;
+        self.fit();
+    }
+
+    /// Changes the cutoff frequency of the lowpass filter in the feedback path, in Hz.
+    ///
+    /// The filter state is kept, so the repeats carry on ringing through the new cutoff.
+    /// The right channel follows, unless `lowpass_filter_right` is set.
+    pub fn set_lowpass(&mut self, freq_hz: f64) {
+        self.settings.lowpass_filter = freq_hz;
+
+        let sample_rate = self.settings.sample_rate as f64;
+        let cutoffs = self.settings.lowpass_cutoffs();
+        for state in core::iter::once(&mut self.state).chain(&mut self.channels) {
+            state.set_lowpass_cutoffs(sample_rate, cutoffs);
+        }
+    }
+
+    /// Changes the cutoff frequency of the highpass filter in the feedback path, in Hz.
+    ///
+    /// The filter state is kept, so the repeats carry on ringing through the new cutoff.
+    /// The right channel follows, unless `highpass_filter_right` is set.
+    pub fn set_highpass(&mut self, freq_hz: f64) {
+        self.settings.highpass_filter = freq_hz;
+
+        let sample_rate = self.settings.sample_rate as f64;
+        let cutoffs = self.settings.highpass_cutoffs();
+        for state in core::iter::once(&mut self.state).chain(&mut self.channels) {
+            state.set_highpass_cutoffs(sample_rate, cutoffs);
+        }
+    }
+
+    /// Returns the peak level of the wet signal, for the left and right channels.
+    ///
+    /// The peak holds the loudest recent sample, falling back over `meter_release_ms`.
+    pub fn peak(&self) -> (f32, f32) {
+        self.state.meter.peak().into()
+    }
+
+    /// Returns the RMS level of the wet signal over the last `meter_rms_window_ms`,
+    /// for the left and right channels.
+    pub fn rms(&self) -> (f32, f32) {
+        self.state.meter.rms().into()
+    }
+
+    /// Returns the linear gain that the feedback filters apply to each repeat at
+    /// `freq_hz`, for drawing the filter curve in a UI.
+    ///
+    /// This combines the lowpass, highpass, shelving and peaking filters, and the DC
+    /// blocker when it's enabled. It doesn't include the feedback level, and
+    /// follows the left channel's cutoffs when the right's differ. With
+    /// [`FilterRouting::Parallel`], the two filters' gains are averaged without
+    /// accounting for their phase, so the curve is only approximate.
+    pub fn magnitude_response(&self, freq_hz: f64) -> f64 {
+        let sample_rate = self.settings.sample_rate as f64;
+        let state = &self.state;
+
+        let lowpass = state
+            .lowpass_filter
+            .magnitude_response(freq_hz, sample_rate);
+        let highpass = state
+            .highpass_filter
+            .magnitude_response(freq_hz, sample_rate);
+        let filters = match (
+            self.settings.lowpass_enabled,
+            self.settings.highpass_enabled,
+        ) {
+            (true, true) => match self.settings.filter_routing {
+                FilterRouting::LpThenHp | FilterRouting::HpThenLp => lowpass * highpass,
+                FilterRouting::Parallel => (lowpass + highpass) / 2.0,
+            },
+            (true, false) => lowpass,
+            (false, true) => highpass,
+            (false, false) => 1.0,
+        };
+
+        let tilt = match &state.tilt_filter {
+            Some((low_shelf, high_shelf)) => {
+                low_shelf.magnitude_response(freq_hz, sample_rate)
+                    * high_shelf.magnitude_response(freq_hz, sample_rate)
+            }
+            None => 1.0,
+        };
+        let brighten = match brighten_gain(
+            self.settings.repeat_brighten_db,
+            self.settings.effective_feedback(),
+        ) {
+            Some(gain) => {
+                let mut brighten_filter = state.brighten_filter.clone();
+                brighten_filter.set_shelf_gain(gain as f64);
+                brighten_filter.magnitude_response(freq_hz, sample_rate)
+            }
+            None => 1.0,
+        };
+        let peak = match &state.peak_filter {
+            Some(peak_filter) => peak_filter.magnitude_response(freq_hz, sample_rate),
+            None => 1.0,
+        };
+        let band = match &state.band_filter {
+            Some(band_filter) => band_filter.magnitude_response(freq_hz, sample_rate),
+            None => 1.0,
+        };
+        let dc_block = match self.settings.dc_block {
+            true => state.dc_blocker.magnitude_response(freq_hz, sample_rate),
+            false => 1.0,
+        };
+
+        filters * tilt * brighten * peak * band * dc_block
+    }
+
+    /// Returns how many frames have produced a NaN or infinity in the feedback
+    /// path since the delay was created.
+    ///
+    /// Those frames are silenced, and the filters in the feedback path are
+    /// cleared, so a momentary glitch can't poison the delay line. A count that
+    /// keeps rising points at a bad parameter or input. It isn't cleared by
+    /// [`Delay::reset`].
+    pub fn nan_count(&self) -> u64 {
+        core::iter::once(&self.state)
+            .chain(&self.channels)
+            .map(|state| state.nan_count)
+            .sum()
+    }
+
+    /// Returns the processing latency of the wet path, in frames.
+    ///
+    /// Hosts can use this for plugin delay compensation. The dry signal is
+    /// delayed by the same amount, so the dry and wet signals stay in phase when
+    /// they're mixed. Oversampling the saturation delays the feedback path by 16
+    /// samples, and diffusion by about 12 ms, which the repeats are read early to
+    /// make up for, so this is usually 0. Only delay times shorter than that can't be made up, and the
+    /// difference is reported here.
+    pub fn latency_samples(&self) -> usize {
+        self.settings.wet_latency()
+    }
+
+    /// Estimates how many frames it takes for the delay tail to decay below -60dB
+    /// once the input falls silent, up to five minutes.
+    ///
+    /// While frozen, the buffer loops forever and the tail never ends, so this
+    /// returns `usize::MAX`.
+    pub fn tail_samples(&self) -> usize {
+        if self.settings.freeze {
+            return usize::MAX;
+        }
+
+        let feedback = self.settings.effective_feedback();
+        if feedback <= 0.0 {
+            return 0;
+        }
+
+        // Each repeat is scaled by the feedback once more than the previous one.
+        let repeats = ceilf(lnf(TAIL_THRESHOLD) / lnf(feedback)) as usize;
+        let max_tail = (MAX_TAIL_MS / 1000.0 * self.settings.sample_rate) as usize;
+
+        repeats
+            .saturating_mul(self.settings.delay_buffer_len())
+            .min(max_tail)
+    }
+
+    /// Flushes the tail still ringing once the input has stopped, writing it to
+    /// the output buffer as interleaved stereo samples.
+    ///
+    /// This is the same as [`Delay::render_tail`]. Size `output` with
+    /// [`Delay::tail_samples`] to flush the whole tail.
+    #[doc(alias = "flush_to")]
+    pub fn flush(&mut self, output: &mut [T]) {
+        self.render_tail(output);
+    }
+
+    /// Feeds a unit impulse through the delay and returns the first `len` output
+    /// frames, as `(left, right)` pairs.
+    ///
+    /// The delay is reset before and after, so the response doesn't depend on
+    /// what was processed before, and doesn't leave a tail behind. The settings
+    /// are left untouched. This allocates, so it's meant for testing and analysis
+    /// rather than the audio thread.
+    ///
+    /// ```rust
+    /// use aych_delay::{Delay, Settings};
+    ///
+    /// let mut delay: Delay = Delay::new(Settings {
+    ///     sample_rate: 48000.0,
+    ///     delay_time: 10.0,
+    ///     ..Settings::default()
+    /// });
+    ///
+    /// let response = delay.impulse_response(1000);
+    /// let first_repeat = (1..1000).find(|&i| {
+    ///     let (left, right) = response[i];
+    ///     left.abs().max(right.abs()) > 0.01
+    /// });
+    /// assert_eq!(first_repeat, Some(480));
+    /// ```
+    pub fn impulse_response(&mut self, len: usize) -> Vec<(T, T)> {
+        self.reset();
+
+        let response = (0..len)
+            .map(|i| {
+                let input = match i {
+                    0 => Frame::new(T::ONE, T::ONE),
+                    _ => Frame::default(),
+                };
+
+                self.process_frame(input).into()
+            })
+            .collect();
+
+        self.reset();
+
+        response
+    }
+
+    /// Feeds silence through the delay and writes the decaying tail to the output buffer.
+    ///
+    /// `output` holds interleaved stereo samples. Use [`Delay::tail_samples`] to size
+    /// it for the full tail. This flushes whatever is still ringing at the end of a
+    /// clip, the same as calling [`Delay::process`] with a silent input.
+    pub fn render_tail(&mut self, output: &mut [T]) {
+        let frames = output
+            .chunks_mut(2)
+            .map(|output_frame| (Frame::default(), output_frame));
+        self.process_frames(frames, write_interleaved);
+    }
+
+    /// Processes the input buffer and writes the updated signal to the output buffer.
+    ///
+    /// Both buffers hold interleaved stereo samples. If `input` has an odd length,
+    /// the trailing sample is treated as a stereo frame with a silent right channel.
+    /// If `output` has an odd length, the right channel of its last frame is dropped.
+    ///
+    /// Returns the number of frames written. If `input` is shorter than `output`,
+    /// the rest of `output` is filled with silence. If it's longer, the extra
+    /// input is not processed, which the returned count makes detectable.
+    ///
+    /// Processing doesn't allocate, so it's safe to call from a real-time audio
+    /// callback. Only the setters that resize the delay buffer allocate.
+    #[doc(alias = "process_f64")]
+    pub fn process(&mut self, input: &[T], output: &mut [T]) -> usize {
+        // Walk the interleaved buffers in stereo pairs, without collecting them.
+        let frames = input
+            .chunks(2)
+            .zip(output.chunks_mut(2))
+            .map(|(input_frame, output_frame)| (interleaved_frame(input_frame), output_frame));
+        let frames = self.process_frames(frames, write_interleaved);
+
+        // Silence anything the input didn't reach, rather than leaving stale samples.
+        let written = (frames * 2).min(output.len());
+        output[written..].fill(T::ZERO);
+
+        frames
+    }
+
+    /// Processes the input buffer like [`Delay::process`], but ducks the wet signal
+    /// from a separate sidechain signal rather than from the input.
+    ///
+    /// `sidechain` holds interleaved stereo samples, like `input`, and uses the
+    /// same `duck_amount`, `duck_attack_ms` and `duck_release_ms` settings. If it's
+    /// shorter than `input`, the rest of the sidechain is treated as silence.
+    ///
+    /// Returns the number of frames written.
+    pub fn process_with_sidechain(
+        &mut self,
+        input: &[T],
+        sidechain: &[T],
+        output: &mut [T],
+    ) -> usize {
+        let mut frames = 0;
+        let mut sidechain = sidechain.chunks(2).map(interleaved_frame);
+
+        for (input_frame, output_frame) in input.chunks(2).zip(output.chunks_mut(2)) {
+            let input_sample = interleaved_frame(input_frame);
+            let sidechain_sample = sidechain.next().unwrap_or_default();
+            let output_sample = self.state.process_frame(
+                &self.settings,
+                input_sample,
+                sidechain_sample,
+                self.settings.ping_pong,
             );
 
-            // Apply output level by scaling the delayed sample by the current output level.
-            let delay_sample = (
-                delay_sample.0 * self.settings.output_level,
-                delay_sample.1 * self.settings.output_level,
+            write_interleaved(output_frame, output_sample);
+
+            frames += 1;
+        }
+
+        // Silence anything the input didn't reach, rather than leaving stale samples.
+        let written = (frames * 2).min(output.len());
+        output[written..].fill(T::ZERO);
+
+        frames
+    }
+
+    /// Processes the input buffer like [`Delay::process`], while an external
+    /// control signal modulates one parameter, like a hardware pedal's CV input.
+    ///
+    /// `control` holds one value per stereo frame, usually between -1.0 and 1.0,
+    /// which is scaled by `depth` and added to the `target` parameter's setting,
+    /// so the setting is the offset the control moves around. A `depth` of 0.0
+    /// leaves the delay unmodulated. If `control` is shorter than the input, the
+    /// rest of the frames are unmodulated too.
+    ///
+    /// The delay time can't be moved beyond the delay buffer, so create the delay
+    /// with [`Delay::with_max_delay`] to leave room for it to move. The settings
+    /// are left as they were, and only one parameter is modulated at a time.
+    ///
+    /// Returns the number of frames written.
+    pub fn process_modulated(
+        &mut self,
+        input: &[T],
+        control: &[f32],
+        target: ModTarget,
+        depth: f32,
+        output: &mut [T],
+    ) -> usize {
+        let mut frames = 0;
+        let mut control = control.iter();
+
+        for (input_frame, output_frame) in input.chunks(2).zip(output.chunks_mut(2)) {
+            let input_sample = interleaved_frame(input_frame);
+            let value = control.next().copied().unwrap_or(0.0) * depth;
+
+            self.state.control = Some((target, value));
+            let output_sample = self.process_frame(input_sample);
+
+            write_interleaved(output_frame, output_sample);
+
+            frames += 1;
+        }
+        self.state.control = None;
+
+        // Silence anything the input didn't reach, rather than leaving stale samples.
+        let written = (frames * 2).min(output.len());
+        output[written..].fill(T::ZERO);
+
+        frames
+    }
+
+    /// Processes the input buffer like [`Delay::process`], but adds the updated
+    /// signal on top of what's already in the output buffer, rather than
+    /// overwriting it.
+    ///
+    /// This suits an aux send, where the delay's output is summed into a return
+    /// bus. The output still follows `dry_wet_mix` and `wet_only`, so use
+    /// [`Delay::solo_wet`] to add only the wet signal. The output beyond the end
+    /// of the input is left untouched.
+    ///
+    /// Returns the number of frames added.
+    pub fn process_add(&mut self, input: &[T], output: &mut [T]) -> usize {
+        let mut frames = 0;
+
+        for (input_frame, output_frame) in input.chunks(2).zip(output.chunks_mut(2)) {
+            let input_sample = interleaved_frame(input_frame);
+            let output_sample = self.process_frame(input_sample);
+
+            output_frame[0] = output_frame[0] + output_sample.l;
+            if let Some(right) = output_frame.get_mut(1) {
+                *right = *right + output_sample.r;
+            }
+
+            frames += 1;
+        }
+
+        frames
+    }
+
+    /// Processes the input buffer like [`Delay::process`], but writes the dry and
+    /// wet signals to separate output buffers, rather than mixing them.
+    ///
+    /// This lets the host balance and process the two signals itself. The dry
+    /// signal includes the chorus, if there is one, and the wet signal includes
+    /// the ducking. `dry_wet_mix`, `wet_only`, `output_level`, `balance` and
+    /// `bypass` aren't applied to either.
+    ///
+    /// Returns the number of frames written. If `input` is shorter than the
+    /// output buffers, the rest of them is filled with silence.
+    pub fn process_split(&mut self, input: &[T], dry_out: &mut [T], wet_out: &mut [T]) -> usize {
+        let mut frames = 0;
+
+        for ((input_frame, dry_frame), wet_frame) in input
+            .chunks(2)
+            .zip(dry_out.chunks_mut(2))
+            .zip(wet_out.chunks_mut(2))
+        {
+            let input_sample = interleaved_frame(input_frame);
+            let (dry_sample, wet_sample) = self.state.process_split(
+                &self.settings,
+                input_sample,
+                input_sample,
+                self.settings.ping_pong,
             );
 
-            // Write the delayed sample to the output buffer.
-            output[output_index * 2] = delay_sample.0;
-            output[output_index * 2 + 1] = delay_sample.1;
+            for (frame, sample) in [(dry_frame, dry_sample), (wet_frame, wet_sample)] {
+                frame[0] = sample.l;
+                if let Some(right) = frame.get_mut(1) {
+                    *right = sample.r;
+                }
+            }
+
+            frames += 1;
+        }
+
+        // Silence anything the input didn't reach, rather than leaving stale samples.
+        for output in [dry_out, wet_out] {
+            let written = (frames * 2).min(output.len());
+            output[written..].fill(T::ZERO);
+        }
+
+        frames
+    }
+
+    /// Processes a buffer of interleaved stereo samples, overwriting it with the updated signal.
+    ///
+    /// Each frame is read before it's written, so the dry signal is still
+    /// available for the dry/wet mix. An odd trailing sample is treated as a
+    /// left sample with a silent right channel.
+    pub fn process_in_place(&mut self, buffer: &mut [T]) {
+        let frames = buffer
+            .chunks_mut(2)
+            .map(|frame| (interleaved_frame(frame), frame));
+        self.process_frames(frames, write_interleaved);
+    }
+
+    /// Processes a buffer of `(left, right)` frames, overwriting it with the updated signal.
+    ///
+    /// This is the same as [`Delay::process_in_place`], for audio that's already
+    /// held as stereo pairs, so there's no odd trailing sample to deal with.
+    pub fn process_stereo_frames(&mut self, frames: &mut [(T, T)]) {
+        let frames = frames.iter_mut().map(|frame| (Frame::from(*frame), frame));
+        self.process_frames(frames, |frame, output_sample| *frame = output_sample.into());
+    }
+
+    /// Lazily processes an iterator of `(left, right)` frames, yielding the updated frames.
+    ///
+    /// Each frame is processed as it's pulled from the returned iterator.
+    pub fn process_iter<'a>(
+        &'a mut self,
+        input: impl Iterator<Item = (T, T)> + 'a,
+    ) -> impl Iterator<Item = (T, T)> + 'a {
+        input.map(move |frame| self.process_frame(frame.into()).into())
+    }
+
+    /// Processes a buffer of mono samples and writes the updated signal to the output buffer.
+    ///
+    /// The mono signal runs through the same delay line, feedback and filters as
+    /// the stereo path. Ping-pong is skipped, since there is no stereo image to
+    /// bounce between, and the two channels are summed back down to mono.
+    pub fn process_mono(&mut self, input: &[T], output: &mut [T]) {
+        for (input_sample, output_sample) in input.iter().zip(output.iter_mut()) {
+            let frame = self.process_frame_with(Frame::mono(*input_sample), false);
+
+            *output_sample = (frame.l + frame.r) / T::from_f32(2.0);
+        }
+    }
+
+    /// Processes a single stereo frame and returns the updated frame.
+    pub fn process_frame(&mut self, input_sample: Frame<T>) -> Frame<T> {
+        self.process_frame_with(input_sample, self.settings.ping_pong)
+    }
+
+    /// Processes planar audio with any number of channels, overwriting each
+    /// channel's buffer with the updated signal.
+    ///
+    /// Exactly two channels are processed as stereo, the same as [`Delay::process`].
+    /// Any other number of channels each get their own mono delay line and filters,
+    /// without ping-pong. Those extra delay lines are allocated the first time a
+    /// channel count is seen, so call this once outside the audio thread to
+    /// avoid allocating on it. Only the first channel is metered.
+    ///
+    /// Channels of different lengths are processed up to the shortest one.
+    pub fn process_planar(&mut self, channels: &mut [&mut [T]]) {
+        let frames = channels
+            .iter()
+            .map(|channel| channel.len())
+            .min()
+            .unwrap_or(0);
+
+        if let [left, right] = channels {
+            return self.process_block_planar(left, right);
+        }
+
+        // The first channel uses the main delay line, and the rest get their own.
+        let extra_channels = channels.len().saturating_sub(1);
+        if self.channels.len() != extra_channels {
+            let max_delay_ms = self.state.max_delay_ms;
+            self.channels.resize_with(extra_channels, || {
+                State::with_max_delay(&self.settings, max_delay_ms)
+            });
+        }
+
+        for (index, channel) in channels.iter_mut().enumerate() {
+            let state = match index {
+                0 => &mut self.state,
+                _ => &mut self.channels[index - 1],
+            };
+
+            for sample in channel[..frames].iter_mut() {
+                let input_sample = Frame::mono(*sample);
+                let frame = state.process_frame(&self.settings, input_sample, input_sample, false);
+                *sample = (frame.l + frame.r) / T::from_f32(2.0);
+            }
+        }
+    }
+
+    /// Processes a block of stereo audio held in separate left and right buffers,
+    /// overwriting them with the updated signal.
+    ///
+    /// This is the same as [`Delay::process`], without having to interleave and
+    /// deinterleave the channels that most plugin hosts hand over. Buffers of
+    /// different lengths are processed up to the shorter one.
+    pub fn process_block_planar(&mut self, left: &mut [T], right: &mut [T]) {
+        // The dry filters can run a block at a time while their cutoffs hold still.
+        if self.settings.filter_dry && self.settings.filter_env_amount == 0.0 {
+            let frames = left.len().min(right.len());
+            let blocks = left[..frames]
+                .chunks_mut(DRY_BLOCK)
+                .zip(right[..frames].chunks_mut(DRY_BLOCK));
+            for (left, right) in blocks {
+                self.state
+                    .process_block_filtered(&self.settings, left, right);
+            }
+
+            return;
+        }
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let output_sample = self.process_frame(Frame::new(*l, *r));
+            (*l, *r) = output_sample.into();
+        }
+    }
+
+    // Resizes every delay line to fit the settings.
+    fn fit(&mut self) {
+        self.state.fit(&self.settings);
+        for channel in &mut self.channels {
+            channel.fit(&self.settings);
+        }
+    }
+
+    // Runs each input frame through the delay in turn, handing the output frame
+    // to `write` with the slot it's paired with. Returns the number of frames.
+    // Every buffer layout shares this loop, so they all process the same way.
+    fn process_frames<O>(
+        &mut self,
+        frames: impl Iterator<Item = (Frame<T>, O)>,
+        mut write: impl FnMut(O, Frame<T>),
+    ) -> usize {
+        let mut count = 0;
+        for (input_sample, output) in frames {
+            write(output, self.process_frame(input_sample));
+            count += 1;
+        }
+
+        count
+    }
+
+    fn process_frame_with(&mut self, input_sample: Frame<T>, ping_pong: bool) -> Frame<T> {
+        self.state
+            .process_frame(&self.settings, input_sample, input_sample, ping_pong)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Returns the frame where the left channel of interleaved samples peaks.
+    fn peak_frame(samples: &[f32]) -> usize {
+        (0..samples.len() / 2)
+            .max_by(|&a, &b| samples[a * 2].abs().total_cmp(&samples[b * 2].abs()))
+            .unwrap()
+    }
+
+    // Feeds an impulse through the delay, returning where the dry and wet signals peak.
+    fn impulse_peaks(settings: Settings) -> (usize, usize) {
+        let mut delay = Delay::new(settings);
+        let mut input = vec![0.0; 4000];
+        input[..2].fill(1.0);
+        let mut dry = vec![0.0; input.len()];
+        let mut wet = vec![0.0; input.len()];
+        delay.process_split(&input, &mut dry, &mut wet);
+
+        (peak_frame(&dry), peak_frame(&wet))
+    }
+
+    // Settings that send the repeats straight back, so the impulse stays on the left.
+    fn straight(delay_time: f32) -> Settings {
+        Settings {
+            delay_time,
+            ping_pong: false,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn oversampling_latency_is_compensated() {
+        let oversample = |settings: Settings| Settings {
+            saturation: Saturation::Tanh,
+            oversample: 4,
+            ..settings
+        };
+
+        // Long enough delays read early, so the repeats land where they would without it.
+        let settings = straight(10.0);
+        assert_eq!(
+            impulse_peaks(oversample(settings.clone())),
+            impulse_peaks(settings)
+        );
+        assert_eq!(Delay::new(oversample(straight(10.0))).latency_samples(), 0);
+
+        // Shorter delays can't be read early enough, so the dry signal is delayed to match.
+        let settings = oversample(straight(0.1));
+        let latency = Delay::new(settings.clone()).latency_samples();
+        let (dry, wet) = impulse_peaks(settings);
+        assert!(latency > 0);
+        assert_eq!(dry, latency);
+        assert_eq!(wet - dry, impulse_peaks(straight(0.1)).1);
+    }
+
+    #[test]
+    fn diffuser_latency_is_compensated() {
+        let diffuse = |settings: Settings| Settings {
+            diffusion: 0.001,
+            ..settings
+        };
+
+        // The diffuser is about 12 ms long, so it's made up for on longer delays.
+        let settings = straight(30.0);
+        assert_eq!(
+            impulse_peaks(diffuse(settings.clone())),
+            impulse_peaks(settings)
+        );
+        assert_eq!(Delay::new(diffuse(straight(30.0))).latency_samples(), 0);
+
+        // And shorter ones delay the dry signal to match.
+        let settings = diffuse(straight(5.0));
+        assert!(Delay::new(settings).latency_samples() > 0);
+    }
+
+    #[test]
+    fn bypass_passes_the_input_exactly() {
+        let mut delay = Delay::new(straight(10.0));
+        let input: Vec<f32> = (0..48000).map(|i| sinf(i as f32 * 0.01)).collect();
+        let mut output = vec![0.0; input.len()];
+        delay.process(&input, &mut output);
+
+        // Switch over while the repeats are still ringing, and let the crossfade settle.
+        delay.set_bypass(true);
+        delay.process(&input, &mut output);
+        delay.process(&input, &mut output);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn rejects_invalid_sample_rates() {
+        let settings = |sample_rate| Settings {
+            sample_rate,
+            ..Settings::default()
+        };
+        for sample_rate in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                settings(sample_rate).validate(),
+                Err(SettingsError::InvalidSampleRate(_))
+            ));
+
+            let mut delay = Delay::new(settings(sample_rate));
+            assert_eq!(delay.settings.sample_rate, DEFAULT_SAMPLE_RATE);
+            delay.set_sample_rate(sample_rate);
+            assert_eq!(delay.settings.sample_rate, DEFAULT_SAMPLE_RATE);
+        }
+
+        // Rates too low for the filters' minimum cutoff still run.
+        let mut delay = Delay::new(settings(5.0));
+        let mut output = [0.0; 64];
+        delay.process(&[0.5; 64], &mut output);
+    }
+
+    #[test]
+    fn tail_is_capped() {
+        let delay = Delay::new(Settings {
+            delay_time: 60_000.0,
+            feedback: 1.0,
+            ..Settings::default()
+        });
+        let max_tail = (MAX_TAIL_MS / 1000.0 * DEFAULT_SAMPLE_RATE) as usize;
+        assert_eq!(delay.tail_samples(), max_tail);
+    }
+
+    #[test]
+    fn block_filtering_matches_frames() {
+        let settings = Settings {
+            delay_time: 3.0,
+            filter_dry: true,
+            highpass_enabled: true,
+            highpass_filter: 300.0,
+            filter_routing: FilterRouting::Parallel,
+            ..Settings::default()
+        };
+        let input: Vec<f32> = (0..1000).map(|i| sinf(i as f32 * 0.37)).collect();
+
+        let mut delay = Delay::new(settings.clone());
+        let frames: Vec<Frame<f32>> = input
+            .iter()
+            .map(|&sample| delay.process_frame(Frame::new(sample, -sample)))
+            .collect();
 
-            // Increment the input and output buffer indices.
-            input_index += 1;
-            output_index += 1;
+        // Uneven block lengths, so some span more than one filter block.
+        let mut delay = Delay::new(settings);
+        let (mut left, mut right): (Vec<f32>, Vec<f32>) =
+            input.iter().map(|&sample| (sample, -sample)).unzip();
+        let (left_head, left_tail) = left.split_at_mut(100);
+        let (right_head, right_tail) = right.split_at_mut(100);
+        delay.process_block_planar(left_head, right_head);
+        delay.process_block_planar(left_tail, right_tail);
 
-            // Increment the delay buffer index and wrap around if necessary.
-            self.state.delay_buffer_index =
-                (self.state.delay_buffer_index + 1) % self.state.delay_buffer.len();
+        for (frame, (l, r)) in frames.iter().zip(left.iter().zip(&right)) {
+            assert_eq!(*frame, Frame::new(*l, *r));
         }
     }
 }
@@ -0,0 +1,42 @@
+// Floating point functions that aren't available in `core`.
+//
+// With the `std` feature these use the standard library, and without it they
+// fall back to `libm`, so the crate can run on targets without an OS.
+
+macro_rules! float_fns {
+    ($($name:ident($($arg:ident),+): $ty:ty => $method:ident, $libm:ident;)*) => {
+        $(
+            #[inline]
+            pub(crate) fn $name($($arg: $ty),+) -> $ty {
+                #[cfg(feature = "std")]
+                return <$ty>::$method($($arg),+);
+
+                #[cfg(not(feature = "std"))]
+                return libm::$libm($($arg),+);
+            }
+        )*
+    };
+}
+
+float_fns! {
+    atan(x): f64 => atan, atan;
+    atanf(x): f32 => atan, atanf;
+    ceilf(x): f32 => ceil, ceilf;
+    cos(x): f64 => cos, cos;
+    cosf(x): f32 => cos, cosf;
+    expf(x): f32 => exp, expf;
+    floor(x): f64 => floor, floor;
+    floorf(x): f32 => floor, floorf;
+    lnf(x): f32 => ln, logf;
+    log10f(x): f32 => log10, log10f;
+    pow(x, y): f64 => powf, pow;
+    powf(x, y): f32 => powf, powf;
+    sin(x): f64 => sin, sin;
+    sinf(x): f32 => sin, sinf;
+    sqrt(x): f64 => sqrt, sqrt;
+    sqrtf(x): f32 => sqrt, sqrtf;
+    tan(x): f64 => tan, tan;
+    tanh(x): f64 => tanh, tanh;
+    tanhf(x): f32 => tanh, tanhf;
+    truncf(x): f32 => trunc, truncf;
+}
@@ -0,0 +1,81 @@
+use crate::math::sqrtf;
+use crate::smoother::Smoother;
+use crate::Frame;
+
+// A level meter for one channel, tracking a decaying peak and a windowed RMS.
+#[derive(Clone)]
+struct ChannelMeter {
+    peak: Smoother,
+    mean_square: Smoother,
+}
+
+impl ChannelMeter {
+    fn new(rms_window_ms: f32, release_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            peak: Smoother::new(0.0, release_ms, sample_rate),
+            mean_square: Smoother::new(0.0, rms_window_ms, sample_rate),
+        }
+    }
+
+    fn process(&mut self, sample: f32) {
+        // The peak jumps up instantly, and falls back over the release time.
+        let level = sample.abs();
+        match level > self.peak.value() {
+            true => self.peak.reset(level),
+            false => {
+                self.peak.next(0.0);
+            }
+        }
+
+        self.mean_square.next(sample * sample);
+    }
+
+    fn reset(&mut self) {
+        self.peak.reset(0.0);
+        self.mean_square.reset(0.0);
+    }
+}
+
+// A stereo level meter.
+#[derive(Clone)]
+pub(crate) struct Meter {
+    left: ChannelMeter,
+    right: ChannelMeter,
+}
+
+impl Meter {
+    pub fn new(rms_window_ms: f32, release_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            left: ChannelMeter::new(rms_window_ms, release_ms, sample_rate),
+            right: ChannelMeter::new(rms_window_ms, release_ms, sample_rate),
+        }
+    }
+
+    pub fn set_times(&mut self, rms_window_ms: f32, release_ms: f32, sample_rate: f32) {
+        for meter in [&mut self.left, &mut self.right] {
+            meter.peak.set_time(release_ms, sample_rate);
+            meter.mean_square.set_time(rms_window_ms, sample_rate);
+        }
+    }
+
+    pub fn process(&mut self, sample: Frame) {
+        self.left.process(sample.l);
+        self.right.process(sample.r);
+    }
+
+    pub fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+
+    pub fn peak(&self) -> Frame {
+        Frame::new(self.left.peak.value(), self.right.peak.value())
+    }
+
+    pub fn rms(&self) -> Frame {
+        Frame::new(
+            sqrtf(self.left.mean_square.value()),
+            sqrtf(self.right.mean_square.value()),
+        )
+    }
+}
@@ -0,0 +1,153 @@
+use crate::math::{cos, sin};
+use crate::{Frame, Sample};
+use core::f64::consts::PI;
+
+// The most the feedback path can be oversampled by.
+const MAX_FACTOR: usize = 4;
+
+// The length of the interpolation and decimation filters at each factor, which
+// keeps the filters equally steep relative to the original sample rate.
+const TAPS_PER_FACTOR: usize = 16;
+const MAX_TAPS: usize = TAPS_PER_FACTOR * MAX_FACTOR + 1;
+
+// The lowpass cutoff of the filters, as a fraction of the original Nyquist frequency.
+const CUTOFF: f64 = 0.9;
+
+// How many samples, at the original rate, the signal is delayed by the two filters.
+pub(crate) const LATENCY: usize = TAPS_PER_FACTOR;
+
+// Rounds the oversampling factor down to one that's supported.
+pub(crate) fn supported_factor(factor: u32) -> usize {
+    match factor {
+        0..=1 => 1,
+        2..=3 => 2,
+        _ => 4,
+    }
+}
+
+// A linear phase lowpass FIR filter, with a fixed capacity so it can be
+// redesigned without allocating.
+#[derive(Clone)]
+struct Fir<T> {
+    history: [Frame<T>; MAX_TAPS],
+    index: usize,
+}
+
+impl<T: Sample> Fir<T> {
+    fn new() -> Self {
+        Self {
+            history: [Frame::default(); MAX_TAPS],
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, input: Frame<T>, taps: usize) {
+        self.index = (self.index + 1) % taps;
+        self.history[self.index] = input;
+    }
+
+    fn output(&self, coefficients: &[T]) -> Frame<T> {
+        let taps = coefficients.len();
+
+        coefficients
+            .iter()
+            .enumerate()
+            .fold(Frame::default(), |sum, (k, coefficient)| {
+                sum + self.history[(self.index + taps - k) % taps] * *coefficient
+            })
+    }
+}
+
+// Runs a nonlinear process at a multiple of the sample rate, so the harmonics it
+// adds above the original Nyquist frequency are filtered out, rather than
+// aliasing back down into the audible range.
+#[derive(Clone)]
+pub(crate) struct Oversampler<T> {
+    factor: usize,
+    coefficients: [T; MAX_TAPS],
+    up: Fir<T>,
+    down: Fir<T>,
+}
+
+impl<T: Sample> Oversampler<T> {
+    pub fn new(factor: u32) -> Self {
+        let mut oversampler = Self {
+            factor: 1,
+            coefficients: [T::ZERO; MAX_TAPS],
+            up: Fir::new(),
+            down: Fir::new(),
+        };
+        oversampler.set_factor(factor);
+
+        oversampler
+    }
+
+    pub fn reset(&mut self) {
+        self.up = Fir::new();
+        self.down = Fir::new();
+    }
+
+    // Redesigns the filters for a new factor. This doesn't allocate.
+    pub fn set_factor(&mut self, factor: u32) {
+        let factor = supported_factor(factor);
+        if factor == self.factor {
+            return;
+        }
+
+        // A Blackman windowed sinc, normalized to unity gain at DC.
+        let taps = TAPS_PER_FACTOR * factor + 1;
+        let middle = (taps / 2) as f64;
+        let cutoff = CUTOFF / (2.0 * factor as f64);
+        let coefficients: [f64; MAX_TAPS] = core::array::from_fn(|n| {
+            if n >= taps {
+                return 0.0;
+            }
+
+            let x = n as f64 - middle;
+            let sinc = match x == 0.0 {
+                true => 2.0 * cutoff,
+                false => sin(2.0 * PI * cutoff * x) / (PI * x),
+            };
+            let phase = 2.0 * PI * n as f64 / (taps - 1) as f64;
+            let window = 0.42 - 0.5 * cos(phase) + 0.08 * cos(2.0 * phase);
+
+            sinc * window
+        });
+        let sum: f64 = coefficients.iter().sum();
+
+        self.factor = factor;
+        self.coefficients = coefficients.map(|coefficient| T::from_f64(coefficient / sum));
+        self.reset();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.factor > 1
+    }
+
+    // Upsamples the frame, runs `process` on every oversampled frame, and
+    // decimates the result back down, `LATENCY` samples later.
+    pub fn process(&mut self, input: Frame<T>, process: impl Fn(Frame<T>) -> Frame<T>) -> Frame<T> {
+        let taps = TAPS_PER_FACTOR * self.factor + 1;
+        let coefficients = &self.coefficients[..taps];
+        let gain = T::from_f32(self.factor as f32);
+        let mut output = Frame::default();
+
+        for phase in 0..self.factor {
+            // Stuff zeros between the input frames, making up for their lost energy.
+            let upsampled = match phase {
+                0 => input * gain,
+                _ => Frame::default(),
+            };
+            self.up.push(upsampled, taps);
+            let processed = process(self.up.output(coefficients));
+
+            // Only every `factor`th frame is kept, so only that one needs filtering.
+            self.down.push(processed, taps);
+            if phase == 0 {
+                output = self.down.output(coefficients);
+            }
+        }
+
+        output
+    }
+}
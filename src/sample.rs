@@ -0,0 +1,95 @@
+use crate::math;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+/// `Sample` is a floating point type that audio can be processed in.
+///
+/// It's implemented for `f32` and `f64`. Parameters are always `f32`, but the
+/// delay line, filters and mixing all run at the precision of the sample type.
+pub trait Sample:
+    Copy
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + Send
+    + Sync
+    + 'static
+{
+    /// Silence.
+    const ZERO: Self;
+
+    /// Unity gain.
+    const ONE: Self;
+
+    /// Converts an `f32` into this sample type.
+    fn from_f32(value: f32) -> Self;
+
+    /// Converts an `f64` into this sample type.
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts this sample into an `f32`.
+    fn to_f32(self) -> f32;
+
+    /// Converts this sample into an `f64`.
+    fn to_f64(self) -> f64;
+
+    /// Returns the absolute value of the sample.
+    fn abs(self) -> Self;
+
+    /// Returns the hyperbolic tangent of the sample.
+    fn tanh(self) -> Self;
+
+    /// Returns the arctangent of the sample.
+    fn atan(self) -> Self;
+
+    /// Returns whether the sample is neither infinite nor NaN.
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_sample {
+    ($($ty:ident: $tanh:ident, $atan:ident),*) => {
+        $(
+            impl Sample for $ty {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+
+                fn from_f32(value: f32) -> Self {
+                    value as $ty
+                }
+
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+
+                fn to_f32(self) -> f32 {
+                    self as f32
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn abs(self) -> Self {
+                    $ty::abs(self)
+                }
+
+                fn tanh(self) -> Self {
+                    math::$tanh(self)
+                }
+
+                fn atan(self) -> Self {
+                    math::$atan(self)
+                }
+
+                fn is_finite(self) -> bool {
+                    $ty::is_finite(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(f32: tanhf, atanf, f64: tanh, atan);
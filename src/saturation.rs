@@ -0,0 +1,44 @@
+use crate::Sample;
+
+/// `Saturation` is the shape of the soft clipper in the feedback path.
+///
+/// Every curve has unity gain for quiet signals, so the saturation only
+/// colours the repeats as they get louder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Saturation {
+    /// No saturation. The feedback path stays linear.
+    None,
+
+    /// A smooth, tape-like curve.
+    Tanh,
+
+    /// A gentler curve than `Tanh`, with a softer knee.
+    Arctan,
+
+    /// Hard clipping at full scale.
+    HardClip,
+}
+
+impl Saturation {
+    /// Saturates a sample, pushing it into the curve by `drive`.
+    pub fn process<T: Sample>(self, input: T, drive: f32) -> T {
+        // Without any drive there's nothing to push into the curve.
+        if drive <= 0.0 {
+            return input;
+        }
+
+        let drive = T::from_f32(drive);
+        let driven = input * drive;
+        let shaped = match self {
+            Saturation::None => return input,
+            Saturation::Tanh => driven.tanh(),
+            Saturation::Arctan => driven.atan(),
+            Saturation::HardClip if driven > T::ONE => T::ONE,
+            Saturation::HardClip if driven < -T::ONE => -T::ONE,
+            Saturation::HardClip => driven,
+        };
+
+        shaped / drive
+    }
+}
@@ -0,0 +1,80 @@
+use crate::math::expf;
+
+// How close the value has to get to its target before it snaps onto it, so a
+// ramp down to zero doesn't decay into subnormals.
+const EPSILON: f32 = 1e-9;
+
+// A one-pole smoother that ramps a parameter towards its target value,
+// to avoid zipper noise when the parameter changes.
+#[derive(Clone)]
+pub(crate) struct Smoother {
+    value: f32,
+    coefficient: f32,
+    time_ms: f32,
+    sample_rate: f32,
+}
+
+// Returns the one-pole coefficient that reaches ~63% of a step over `time_ms`.
+pub(crate) fn get_coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    let samples = time_ms / 1000.0 * sample_rate;
+
+    match samples > 1.0 {
+        true => 1.0 - expf(-1.0 / samples),
+        false => 1.0,
+    }
+}
+
+impl Smoother {
+    pub fn new(value: f32, time_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            value,
+            coefficient: get_coefficient(time_ms, sample_rate),
+            time_ms,
+            sample_rate,
+        }
+    }
+
+    // Updates the ramp time, only recomputing the coefficient when it changes.
+    pub fn set_time(&mut self, time_ms: f32, sample_rate: f32) {
+        if time_ms != self.time_ms || sample_rate != self.sample_rate {
+            self.coefficient = get_coefficient(time_ms, sample_rate);
+            self.time_ms = time_ms;
+            self.sample_rate = sample_rate;
+        }
+    }
+
+    // Jumps straight to the target value.
+    pub fn reset(&mut self, value: f32) {
+        self.value = value;
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    // Moves the value towards `target`, landing on it exactly once it's close
+    // enough, or once a step is too small to move it at all.
+    pub fn next(&mut self, target: f32) -> f32 {
+        let value = self.value + (target - self.value) * self.coefficient;
+        self.value = match value == self.value || (target - value).abs() < EPSILON {
+            true => target,
+            false => value,
+        };
+
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settles_exactly_on_target() {
+        for (start, target) in [(0.0, 1.0), (1.0, 0.0), (0.3, 4.0), (-2.0, 0.5)] {
+            let mut smoother = Smoother::new(start, 50.0, 48000.0);
+            let settled = (0..4 * 48000).map(|_| smoother.next(target)).last();
+            assert_eq!(settled, Some(target));
+        }
+    }
+}
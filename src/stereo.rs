@@ -0,0 +1,124 @@
+use crate::{Frame, Sample};
+use alloc::{vec, vec::Vec};
+
+/// `StereoMode` is how the stereo width of the delay is controlled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StereoMode {
+    /// `width` crossfades the ping-pong feedback between mono and full ping-pong.
+    PingPong,
+
+    /// The ping-pong feedback runs at full width, and `ms_width` scales the side
+    /// component of the wet signal instead.
+    MidSide,
+}
+
+// Encodes the frame to mid/side, scales the side by `width`, and decodes it back.
+pub(crate) fn mid_side_width<T: Sample>(frame: Frame<T>, width: T) -> Frame<T> {
+    let half = T::from_f32(0.5);
+    let mid = (frame.l + frame.r) * half;
+    let side = (frame.l - frame.r) * half * width;
+
+    Frame::new(mid + side, mid - side)
+}
+
+/// `PhaseReverse` chooses which channels of the repeats have their polarity inverted.
+///
+/// A `bool` converts into it, reversing both channels or neither. With the
+/// `serde` feature, presets that saved it as a `bool` still load.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "PhaseReverseRepr"))]
+pub struct PhaseReverse {
+    /// Whether to invert the left channel.
+    pub left: bool,
+
+    /// Whether to invert the right channel.
+    pub right: bool,
+}
+
+impl PhaseReverse {
+    /// Reverses both channels, or neither.
+    pub const fn both(reverse: bool) -> Self {
+        Self {
+            left: reverse,
+            right: reverse,
+        }
+    }
+
+    // Inverts the chosen channels of a frame.
+    pub(crate) fn apply<T: Sample>(self, frame: Frame<T>) -> Frame<T> {
+        let invert = |reverse: bool, sample: T| match reverse {
+            true => -sample,
+            false => sample,
+        };
+
+        Frame::new(invert(self.left, frame.l), invert(self.right, frame.r))
+    }
+}
+
+impl From<bool> for PhaseReverse {
+    fn from(reverse: bool) -> Self {
+        Self::both(reverse)
+    }
+}
+
+// Accepts both the per-channel form and the single `bool` it replaced.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum PhaseReverseRepr {
+    Both(bool),
+    Channels { left: bool, right: bool },
+}
+
+#[cfg(feature = "serde")]
+impl From<PhaseReverseRepr> for PhaseReverse {
+    fn from(repr: PhaseReverseRepr) -> Self {
+        match repr {
+            PhaseReverseRepr::Both(reverse) => Self::both(reverse),
+            PhaseReverseRepr::Channels { left, right } => Self { left, right },
+        }
+    }
+}
+
+// The longest Haas delay, in milliseconds. Much longer and it's heard as an echo.
+const MAX_HAAS_MS: f32 = 30.0;
+
+// Widens a signal with the Haas effect, by delaying one channel by a few milliseconds.
+#[derive(Clone)]
+pub(crate) struct Haas<T> {
+    buffer: Vec<Frame<T>>,
+    index: usize,
+}
+
+impl<T: Sample> Haas<T> {
+    pub fn new(sample_rate: f32) -> Self {
+        let len = (MAX_HAAS_MS / 1000.0 * sample_rate) as usize + 1;
+
+        Self {
+            buffer: vec![Frame::default(); len],
+            index: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.fill(Frame::default());
+        self.index = 0;
+    }
+
+    // Delays the right channel by `delay_ms`, or the left when it's negative.
+    pub fn process(&mut self, input: Frame<T>, delay_ms: f32, sample_rate: f32) -> Frame<T> {
+        let len = self.buffer.len();
+        self.buffer[self.index] = input;
+
+        let delay = (delay_ms.abs().min(MAX_HAAS_MS) / 1000.0 * sample_rate) as usize;
+        let delayed = self.buffer[(self.index + len - delay.min(len - 1)) % len];
+        self.index = (self.index + 1) % len;
+
+        match delay_ms > 0.0 {
+            true => Frame::new(input.l, delayed.r),
+            false => Frame::new(delayed.l, input.r),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::filters::{Mode, TPTOnePoleStereo};
+use crate::math::{cosf, sinf};
+use crate::{Frame, Sample};
+use core::f32::consts::{FRAC_PI_4, SQRT_2};
+
+/// `Tap` is an extra read position in the delay buffer, used for multi-tap delays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tap {
+    /// How far back the tap reads from the delay buffer, in milliseconds.
+    pub time_ms: f32,
+
+    /// The level of the tap. 1.0 is unity gain.
+    pub gain: f32,
+
+    /// The stereo position of the tap. -1.0 is full left, 1.0 is full right.
+    /// Panning balances the two channels the tap reads with a constant-power
+    /// law, so the tap is as loud anywhere across the stereo field.
+    pub pan: f32,
+
+    /// The cutoff frequency of a lowpass filter on the tap, in Hz, so later taps
+    /// can be made progressively darker. `None` leaves the tap unfiltered.
+    pub lowpass: Option<f64>,
+}
+
+impl Tap {
+    /// Creates a new centred `Tap` at unity gain.
+    pub fn new(time_ms: f32) -> Self {
+        Self {
+            time_ms,
+            gain: 1.0,
+            pan: 0.0,
+            lowpass: None,
+        }
+    }
+
+    // Applies the tap's gain and pan to a sample read from the delay buffer.
+    pub(crate) fn apply<T: Sample>(&self, sample: Frame<T>) -> Frame<T> {
+        // Unity in the centre, rising to +3dB on one side as the other falls silent.
+        let angle = (self.pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+        let (left, right) = (cosf(angle) * SQRT_2, sinf(angle) * SQRT_2);
+
+        Frame::new(sample.l * T::from_f32(left), sample.r * T::from_f32(right))
+            * T::from_f32(self.gain)
+    }
+}
+
+// The lowpass filter on one tap, rebuilt whenever the tap's cutoff changes.
+#[derive(Clone)]
+pub(crate) struct TapFilter<T> {
+    cutoff: Option<f64>,
+    filter: Option<TPTOnePoleStereo<T>>,
+}
+
+impl<T: Sample> TapFilter<T> {
+    pub fn new() -> Self {
+        Self {
+            cutoff: None,
+            filter: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        if let Some(filter) = &mut self.filter {
+            filter.reset();
+        }
+    }
+
+    pub fn process(&mut self, sample: Frame<T>, cutoff: Option<f64>, sample_rate: f64) -> Frame<T> {
+        if cutoff != self.cutoff {
+            self.cutoff = cutoff;
+            match (&mut self.filter, cutoff) {
+                (Some(filter), Some(freq_hz)) => filter.set_cutoffs(sample_rate, freq_hz, freq_hz),
+                (filter, freq_hz) => {
+                    *filter = freq_hz
+                        .map(|freq_hz| TPTOnePoleStereo::new(Mode::LOWPASS, sample_rate, freq_hz))
+                }
+            }
+        }
+
+        match &mut self.filter {
+            Some(filter) => filter.process(sample),
+            None => sample,
+        }
+    }
+}
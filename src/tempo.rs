@@ -0,0 +1,198 @@
+// The slowest tempo a note division is measured at, in beats per minute, so its
+// length stays finite.
+const MIN_BPM: f32 = 1.0;
+
+/// `NoteDivision` is a musical note length used to sync the delay time to a tempo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoteDivision {
+    /// A whole note (four beats).
+    Whole,
+    /// A half note (two beats).
+    Half,
+    /// A quarter note (one beat).
+    Quarter,
+    /// An eighth note.
+    Eighth,
+    /// A sixteenth note.
+    Sixteenth,
+    /// A dotted quarter note (one and a half beats).
+    DottedQuarter,
+    /// A dotted eighth note.
+    DottedEighth,
+    /// A dotted sixteenth note.
+    DottedSixteenth,
+    /// A quarter note triplet (three in the space of two beats).
+    QuarterTriplet,
+    /// An eighth note triplet (three in the space of one beat).
+    EighthTriplet,
+    /// A sixteenth note triplet.
+    SixteenthTriplet,
+}
+
+impl NoteDivision {
+    /// Returns the length of the note division, in beats (quarter notes).
+    pub fn beats(self) -> f32 {
+        match self {
+            NoteDivision::Whole => 4.0,
+            NoteDivision::Half => 2.0,
+            NoteDivision::Quarter => 1.0,
+            NoteDivision::Eighth => 0.5,
+            NoteDivision::Sixteenth => 0.25,
+            NoteDivision::DottedQuarter => 1.5,
+            NoteDivision::DottedEighth => 0.75,
+            NoteDivision::DottedSixteenth => 0.375,
+            NoteDivision::QuarterTriplet => 2.0 / 3.0,
+            NoteDivision::EighthTriplet => 1.0 / 3.0,
+            NoteDivision::SixteenthTriplet => 1.0 / 6.0,
+        }
+    }
+
+    /// Returns the length of the note division at the given tempo, in milliseconds.
+    /// Tempos below 1 BPM, and tempos that aren't numbers, are treated as 1 BPM.
+    pub fn to_ms(self, bpm: f32) -> f32 {
+        60_000.0 / bpm.max(MIN_BPM) * self.beats()
+    }
+}
+
+// The most intervals between taps that are averaged.
+const MAX_INTERVALS: usize = 4;
+
+// How far an interval can stray from the average before it's rejected, as a fraction of it.
+const OUTLIER_TOLERANCE: f64 = 0.5;
+
+// How long after the last tap a new one starts over, in milliseconds.
+const DEFAULT_TIMEOUT_MS: f64 = 2000.0;
+
+/// `TapTempo` turns the timestamps of tapped beats into a delay time.
+///
+/// It averages the intervals between the last few taps, ignoring any that stray
+/// too far from the rest, and starts over when there's a long gap between taps.
+/// Two strays in a row that agree with each other are taken as a new tempo, so
+/// it can be changed without pausing.
+///
+/// ```rust
+/// use aych_delay::TapTempo;
+///
+/// let mut tap_tempo = TapTempo::new();
+///
+/// assert_eq!(tap_tempo.tap(0.0), None);
+/// assert_eq!(tap_tempo.tap(500.0), Some(500.0));
+/// assert_eq!(tap_tempo.tap(1010.0), Some(505.0));
+///
+/// // Slowing down to one tap a second is ignored once, then followed.
+/// assert_eq!(tap_tempo.tap(2010.0), Some(505.0));
+/// assert_eq!(tap_tempo.tap(3000.0), Some(995.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TapTempo {
+    last_tap_ms: Option<f64>,
+    intervals: [f64; MAX_INTERVALS],
+    count: usize,
+    next: usize,
+    // The last interval that was rejected, if the one after it hasn't been accepted.
+    rejected: Option<f64>,
+    timeout_ms: f64,
+}
+
+impl TapTempo {
+    /// Creates a new `TapTempo`, which starts over after two seconds without a tap.
+    pub fn new() -> Self {
+        Self::with_timeout(DEFAULT_TIMEOUT_MS)
+    }
+
+    /// Creates a new `TapTempo`, which starts over after `timeout_ms` without a tap.
+    pub fn with_timeout(timeout_ms: f64) -> Self {
+        Self {
+            last_tap_ms: None,
+            intervals: [0.0; MAX_INTERVALS],
+            count: 0,
+            next: 0,
+            rejected: None,
+            timeout_ms,
+        }
+    }
+
+    /// Registers a tap at `time_ms`, a timestamp in milliseconds from any fixed point.
+    ///
+    /// Returns the delay time, in milliseconds, once there have been at least two
+    /// taps. That can be passed straight to [`Delay::set_delay_time`].
+    ///
+    /// [`Delay::set_delay_time`]: crate::Delay::set_delay_time
+    pub fn tap(&mut self, time_ms: f64) -> Option<f32> {
+        let last_tap_ms = self.last_tap_ms.replace(time_ms)?;
+        let interval = time_ms - last_tap_ms;
+
+        // Start over after a long pause, or if the timestamps went backwards.
+        if interval <= 0.0 || interval > self.timeout_ms {
+            self.count = 0;
+            self.next = 0;
+            self.rejected = None;
+
+            return None;
+        }
+
+        let is_outlier = self
+            .average()
+            .is_some_and(|average| !is_near(interval, average));
+        if !is_outlier {
+            self.rejected = None;
+            self.push(interval);
+        } else if let Some(rejected) = self
+            .rejected
+            .filter(|&rejected| is_near(interval, rejected))
+        {
+            // Two strays in a row that agree are a new tempo, so start over from them.
+            self.count = 0;
+            self.next = 0;
+            self.rejected = None;
+            self.push(rejected);
+            self.push(interval);
+        } else {
+            self.rejected = Some(interval);
+        }
+
+        self.delay_time_ms()
+    }
+
+    /// Registers a tap at the given sample position, at the given sample rate.
+    ///
+    /// This is the same as [`TapTempo::tap`], for taps that are timed by the audio clock.
+    pub fn tap_at_sample(&mut self, sample: u64, sample_rate: f32) -> Option<f32> {
+        self.tap(sample as f64 * 1000.0 / sample_rate as f64)
+    }
+
+    /// Returns the current delay time, in milliseconds, if there have been enough taps.
+    pub fn delay_time_ms(&self) -> Option<f32> {
+        self.average().map(|average| average as f32)
+    }
+
+    /// Forgets all the taps so far.
+    pub fn reset(&mut self) {
+        *self = Self::with_timeout(self.timeout_ms);
+    }
+
+    fn push(&mut self, interval: f64) {
+        self.intervals[self.next] = interval;
+        self.next = (self.next + 1) % MAX_INTERVALS;
+        self.count = (self.count + 1).min(MAX_INTERVALS);
+    }
+
+    fn average(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count => Some(self.intervals[..count].iter().sum::<f64>() / count as f64),
+        }
+    }
+}
+
+// Returns whether an interval is within the outlier tolerance of another.
+fn is_near(interval: f64, other: f64) -> bool {
+    (interval - other).abs() <= other * OUTLIER_TOLERANCE
+}
+
+impl Default for TapTempo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,105 @@
+// How long a crossfade between the old and new delay times takes, in milliseconds.
+const CROSSFADE_MS: f32 = 10.0;
+
+/// `TimeChangeMode` is how the delay moves to a new delay time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeChangeMode {
+    /// Jump straight to the new delay time, which can click.
+    Hard,
+
+    /// Briefly read from both the old and new delay times, crossfading between them.
+    /// A change made mid-fade waits for the fade to finish, so automating the
+    /// delay time moves it in steps of one fade. A longer delay time than the
+    /// buffer held waits until enough audio has been written to reach it.
+    Crossfade,
+
+    /// Ramp the read position to the new delay time at a steady speed, bending the
+    /// pitch like a tape machine slowing down or speeding up.
+    Glide {
+        /// How fast the delay time moves, in milliseconds per millisecond. The pitch
+        /// bends by the same fraction while it moves, so 0.1 bends it by 10% and
+        /// covers 100 ms of delay time every second.
+        rate: f32,
+    },
+}
+
+// The left and right delay times to read at, in milliseconds.
+pub(crate) struct DelayTimes {
+    pub current: (f32, f32),
+
+    // The delay times being faded out, and how far the fade to `current` has got.
+    pub previous: (f32, f32),
+    pub fade: f32,
+}
+
+// Tracks the delay times being read, as they follow changes to the settings.
+#[derive(Clone)]
+pub(crate) struct DelayTime {
+    current: (f32, f32),
+    previous: (f32, f32),
+    fade: f32,
+}
+
+impl DelayTime {
+    pub fn new(times: (f32, f32)) -> Self {
+        Self {
+            current: times,
+            previous: times,
+            fade: 1.0,
+        }
+    }
+
+    pub fn reset(&mut self, times: (f32, f32)) {
+        *self = Self::new(times);
+    }
+
+    // The delay times being moved to.
+    pub fn current(&self) -> (f32, f32) {
+        self.current
+    }
+
+    pub fn next(
+        &mut self,
+        mode: TimeChangeMode,
+        target: (f32, f32),
+        sample_rate: f32,
+    ) -> DelayTimes {
+        match mode {
+            TimeChangeMode::Hard => {
+                self.current = target;
+                self.fade = 1.0;
+            }
+            TimeChangeMode::Crossfade => {
+                // Start a new fade once the last one has finished. Starting it
+                // mid-fade would drop the old read while it's still heard.
+                if target != self.current && self.fade >= 1.0 {
+                    self.previous = self.current;
+                    self.current = target;
+                    self.fade = 0.0;
+                }
+
+                let fade_samples = (CROSSFADE_MS / 1000.0 * sample_rate).max(1.0);
+                self.fade = (self.fade + 1.0 / fade_samples).min(1.0);
+            }
+            TimeChangeMode::Glide { rate } => {
+                // Move the delay time by up to `rate` of the time each sample lasts.
+                let step = rate.max(0.0) * 1000.0 / sample_rate;
+                let glide =
+                    |current: f32, target: f32| current + (target - current).clamp(-step, step);
+
+                self.current = (
+                    glide(self.current.0, target.0),
+                    glide(self.current.1, target.1),
+                );
+                self.fade = 1.0;
+            }
+        }
+
+        DelayTimes {
+            current: self.current,
+            previous: self.previous,
+            fade: self.fade,
+        }
+    }
+}
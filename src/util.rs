@@ -0,0 +1,39 @@
+use crate::math::{log10f, powf};
+use crate::Sample;
+
+// Values smaller than this are flushed to zero, before they decay into denormals.
+const DENORMAL_THRESHOLD: f32 = 1e-20;
+
+// Flushes tiny values to zero. Denormal floats are very slow to compute with on
+// some CPUs, and decaying filter state and delay tails eventually produce them.
+pub(crate) fn flush_denormal<T: Sample>(value: T) -> T {
+    match value.abs() < T::from_f32(DENORMAL_THRESHOLD) {
+        true => T::ZERO,
+        false => value,
+    }
+}
+
+// Returns white noise between -1.0 and 1.0, from a xorshift generator.
+pub(crate) fn noise(seed: &mut u32) -> f32 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 17;
+    *seed ^= *seed << 5;
+
+    *seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+// Converts a level in decibels into a linear gain. -inf dB is silence.
+pub(crate) fn db_to_gain(db: f32) -> f32 {
+    match db == f32::NEG_INFINITY {
+        true => 0.0,
+        false => powf(10.0, db / 20.0),
+    }
+}
+
+// Converts a linear gain into a level in decibels. Silence is -inf dB.
+pub(crate) fn gain_to_db(gain: f32) -> f32 {
+    match gain <= 0.0 {
+        true => f32::NEG_INFINITY,
+        false => 20.0 * log10f(gain),
+    }
+}
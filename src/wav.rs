@@ -0,0 +1,121 @@
+//! Offline processing of WAV files, enabled with the `wav` feature.
+
+use crate::{Delay, Settings};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::path::Path;
+
+// How many frames of the tail are rendered at a time.
+const TAIL_CHUNK_FRAMES: usize = 4096;
+
+/// Runs a WAV file through the delay and writes the result to another WAV file.
+///
+/// The input can be mono or stereo, in any integer or float format that `hound`
+/// reads, and the output is written in the same format. The sample rate is
+/// taken from the input file, overriding `settings.sample_rate`. The decaying
+/// tail is rendered after the input ends, so the repeats aren't cut off.
+///
+/// ```no_run
+/// use aych_delay::{wav::process_wav, Settings};
+///
+/// process_wav("dry.wav", "wet.wav", Settings::default()).unwrap();
+/// ```
+pub fn process_wav(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    settings: Settings,
+) -> Result<(), hound::Error> {
+    let mut reader = WavReader::open(input_path)?;
+    let spec = reader.spec();
+    if spec.channels == 0 || spec.channels > 2 {
+        return Err(hound::Error::Unsupported);
+    }
+
+    let input = read_samples(&mut reader, spec)?;
+    let mut writer = WavWriter::create(output_path, spec)?;
+
+    let mut delay = Delay::new(Settings {
+        sample_rate: spec.sample_rate as f32,
+        ..settings
+    });
+
+    // Process the file, then let the tail ring out a chunk at a time. A frozen
+    // loop never ends, so it's rendered once through.
+    let tail = match delay.tail_samples() {
+        usize::MAX => delay.settings.delay_buffer_len(),
+        tail => tail,
+    };
+    let mut output = vec![0.0; input.len()];
+    match spec.channels {
+        1 => delay.process_mono(&input, &mut output),
+        _ => {
+            delay.process(&input, &mut output);
+        }
+    }
+    write_samples(&mut writer, spec, &output)?;
+
+    let channels = spec.channels as usize;
+    let silence = vec![0.0; TAIL_CHUNK_FRAMES];
+    let mut chunk = vec![0.0; TAIL_CHUNK_FRAMES * channels];
+    let mut remaining = tail;
+    while remaining > 0 {
+        let frames = remaining.min(TAIL_CHUNK_FRAMES);
+        let chunk = &mut chunk[..frames * channels];
+        match channels {
+            1 => delay.process_mono(&silence[..frames], chunk),
+            _ => delay.render_tail(chunk),
+        }
+        write_samples(&mut writer, spec, chunk)?;
+        remaining -= frames;
+    }
+
+    writer.finalize()
+}
+
+// Reads every sample in the file, normalized to [-1.0, 1.0].
+fn read_samples<R: std::io::Read>(
+    reader: &mut WavReader<R>,
+    spec: WavSpec,
+) -> Result<Vec<f32>, hound::Error> {
+    match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect(),
+        SampleFormat::Int => {
+            let scale = int_scale(spec.bits_per_sample);
+
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect()
+        }
+    }
+}
+
+// Writes the samples in the same format that they were read in.
+fn write_samples<W: std::io::Write + std::io::Seek>(
+    writer: &mut WavWriter<W>,
+    spec: WavSpec,
+    samples: &[f32],
+) -> Result<(), hound::Error> {
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for &sample in samples {
+                writer.write_sample(sample)?;
+            }
+        }
+        SampleFormat::Int => {
+            let scale = int_scale(spec.bits_per_sample);
+
+            for &sample in samples {
+                // Clip rather than wrap around if the repeats build up past full scale.
+                let sample = (sample * scale).round().clamp(-scale, scale - 1.0);
+                writer.write_sample(sample as i32)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the value of a full scale integer sample.
+fn int_scale(bits_per_sample: u16) -> f32 {
+    (1i64 << (bits_per_sample - 1)) as f32
+}
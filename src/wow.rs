@@ -0,0 +1,66 @@
+use crate::lfo::{Lfo, ModWaveform};
+use crate::smoother::Smoother;
+use crate::util::noise;
+
+// How far the random drift wanders, relative to the combined wow and flutter depth.
+const DRIFT_AMOUNT: f32 = 0.25;
+
+// How quickly the random drift wanders, in milliseconds.
+const DRIFT_TIME_MS: f32 = 50.0;
+
+// The seed of the random drift, so renders are repeatable.
+const DRIFT_SEED: u32 = 0x9e37_79b9;
+
+// Tape-style pitch wobble: a slow "wow" and a fast "flutter" LFO, summed with a
+// little smoothed random drift so the wobble never repeats exactly.
+#[derive(Clone)]
+pub(crate) struct WowFlutter {
+    wow: Lfo,
+    flutter: Lfo,
+    seed: u32,
+    drift: Smoother,
+}
+
+impl WowFlutter {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            wow: Lfo::new(),
+            flutter: Lfo::new(),
+            seed: DRIFT_SEED,
+            drift: Smoother::new(0.0, DRIFT_TIME_MS, sample_rate),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.wow.reset();
+        self.flutter.reset();
+        self.seed = DRIFT_SEED;
+        self.drift.reset(0.0);
+    }
+
+    // Returns the number of milliseconds to offset the delay time by.
+    pub fn next(
+        &mut self,
+        wow_depth_ms: f32,
+        wow_rate_hz: f32,
+        flutter_depth_ms: f32,
+        flutter_rate_hz: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let wow = wow_depth_ms * self.wow.next(wow_rate_hz, sample_rate, ModWaveform::Sine);
+        let flutter = flutter_depth_ms
+            * self
+                .flutter
+                .next(flutter_rate_hz, sample_rate, ModWaveform::Sine);
+
+        self.drift.set_time(DRIFT_TIME_MS, sample_rate);
+        let drift = self.drift.next(noise(&mut self.seed));
+
+        wow + flutter + drift * DRIFT_AMOUNT * (wow_depth_ms.abs() + flutter_depth_ms.abs())
+    }
+
+    // The furthest the wobble can move the delay time, in milliseconds.
+    pub fn max_depth_ms(wow_depth_ms: f32, flutter_depth_ms: f32) -> f32 {
+        (wow_depth_ms.abs() + flutter_depth_ms.abs()) * (1.0 + DRIFT_AMOUNT)
+    }
+}